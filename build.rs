@@ -1,6 +1,8 @@
 fn main() {
-    winres::WindowsResource::new()
-        .set_icon("tlogo.ico")
-        .compile()
-        .unwrap();
+    if std::env::var("CARGO_CFG_TARGET_OS").as_deref() == Ok("windows") {
+        winres::WindowsResource::new()
+            .set_icon("tlogo.ico")
+            .compile()
+            .unwrap();
+    }
 }
@@ -0,0 +1,299 @@
+/*
+    转换引擎的核心: 编码探测和流式转码,
+    不带任何界面依赖, 可以单独嵌进别的工具。
+    egui 前端 (codetranser) 只是它的一个调用方
+*/
+
+use encoding_rs::*;
+use std::io::{Read, Write};
+use std::path::PathBuf;
+
+/* ======================= 探测 ======================= */
+
+/// 探测时参与打分的常见编码, 顺序兼作乱码修复的候选表
+pub static COMMON_ENCODINGS: &[&Encoding] = &[
+    UTF_8,
+    GBK,
+    GB18030,
+    BIG5,
+    SHIFT_JIS,
+    EUC_JP,
+    EUC_KR,
+    WINDOWS_1251,
+    WINDOWS_1252,
+];
+
+/// 该编码的 BOM 字节, 没有约定 BOM 的编码返回 None
+pub fn bom_of(enc: &'static Encoding) -> Option<&'static [u8]> {
+    if enc == UTF_8 {
+        Some(&[0xEF, 0xBB, 0xBF])
+    } else if enc == UTF_16LE {
+        Some(&[0xFF, 0xFE])
+    } else if enc == UTF_16BE {
+        Some(&[0xFE, 0xFF])
+    } else {
+        None
+    }
+}
+
+/// 去掉开头与该编码匹配的 BOM, 不匹配则原样返回
+pub fn strip_bom<'a>(data: &'a [u8], enc: &'static Encoding) -> &'a [u8] {
+    match bom_of(enc) {
+        Some(bom) if data.starts_with(bom) => &data[bom.len()..],
+        _ => data,
+    }
+}
+
+/// 解码结果的"像不像正常文字"打分, 越高越可信
+pub fn plausibility_score(text: &str) -> i64 {
+    let mut score = 0i64;
+    for c in text.chars() {
+        score += match c {
+            '\u{FFFD}' => -8,
+            c if c.is_control() && c != '\n' && c != '\r' && c != '\t' => -4,
+            '\u{4E00}'..='\u{9FFF}' => 3, /* CJK 统一汉字 */
+            '\u{3040}'..='\u{30FF}' => 3, /* 假名 */
+            '\u{AC00}'..='\u{D7AF}' => 3, /* 谚文 */
+            '\u{FF01}'..='\u{FF60}' | '\u{3000}'..='\u{303F}' => 2, /* 全角标点 */
+            c if c.is_ascii_alphanumeric() || c == ' ' => 1,
+            c if c.is_ascii_punctuation() => 1,
+            '\u{0080}'..='\u{00FF}' => -2, /* 拉丁补充区高频出现通常就是乱码 */
+            _ => 0,
+        };
+    }
+    score
+}
+
+/// 探测字节流的编码: BOM 优先, 然后 UTF-8 校验,
+/// 最后把常见编码各解一遍按 [`plausibility_score`] 取最高
+pub fn detect(data: &[u8]) -> &'static Encoding {
+    for enc in [UTF_8, UTF_16LE, UTF_16BE] {
+        if let Some(bom) = bom_of(enc)
+            && data.starts_with(bom)
+        {
+            return enc;
+        }
+    }
+
+    /* 截断采样可能正好切在多字节序列中间, 只有结尾坏按 UTF-8 算 */
+    match std::str::from_utf8(data) {
+        Ok(_) => return UTF_8,
+        Err(e) if e.valid_up_to() + 3 >= data.len() => return UTF_8,
+        Err(_) => {}
+    }
+
+    let mut best = WINDOWS_1252;
+    let mut best_score = i64::MIN;
+    for &enc in COMMON_ENCODINGS {
+        if enc == UTF_8 {
+            continue;
+        }
+        let (text, _) = enc.decode_without_bom_handling(data);
+        let score = plausibility_score(&text);
+        if score > best_score {
+            best_score = score;
+            best = enc;
+        }
+    }
+    best
+}
+
+/* ======================= 流式转码 ======================= */
+
+/// 一次转码的参数; `Default` 是 UTF-8 -> UTF-8 不写 BOM
+#[derive(Clone, Copy)]
+pub struct TranscodeOptions {
+    pub from: &'static Encoding,
+    pub to: &'static Encoding,
+    /// 输出前先写目标编码的 BOM (目标没有约定 BOM 则忽略)
+    pub write_bom: bool,
+}
+
+impl Default for TranscodeOptions {
+    fn default() -> Self {
+        Self {
+            from: UTF_8,
+            to: UTF_8,
+            write_bom: false,
+        }
+    }
+}
+
+/// 流式转码: 任意 `Read` 进、任意 `Write` 出,
+/// 固定大小缓冲, 文件多大内存都不涨
+pub fn transcode_reader_to_writer<R: Read, W: Write>(
+    reader: &mut R,
+    writer: &mut W,
+    opts: &TranscodeOptions,
+) -> std::io::Result<()> {
+    let mut decoder = opts.from.new_decoder();
+    let mut encoder = opts.to.new_encoder();
+
+    if opts.write_bom
+        && let Some(bom) = bom_of(opts.to)
+    {
+        writer.write_all(bom)?;
+    }
+
+    let mut inbuf = [0u8; 16384];
+    let mut text = String::with_capacity(65536);
+    let mut outbuf = vec![0u8; 65536];
+
+    loop {
+        let n = reader.read(&mut inbuf)?;
+        let last = n == 0;
+        let mut src = &inbuf[..n];
+
+        loop {
+            let (result, read, _) = decoder.decode_to_string(src, &mut text, last);
+            src = &src[read..];
+
+            let done = result == CoderResult::InputEmpty;
+            let mut pending = text.as_str();
+            loop {
+                let (eres, eread, written, _) =
+                    encoder.encode_from_utf8(pending, &mut outbuf, last && done);
+                writer.write_all(&outbuf[..written])?;
+                pending = &pending[eread..];
+                if eres == CoderResult::InputEmpty {
+                    break;
+                }
+            }
+            text.clear();
+
+            if done {
+                break;
+            }
+        }
+
+        if last {
+            break;
+        }
+    }
+    writer.flush()
+}
+
+/* ======================= 批量任务 ======================= */
+
+/// 一个文件的转码任务描述, 路径加参数, 随处可以构造、序列化
+#[derive(Clone)]
+pub struct BatchJob {
+    pub input: PathBuf,
+    pub output: PathBuf,
+    pub options: TranscodeOptions,
+}
+
+impl BatchJob {
+    /// 执行任务: 打开输入输出文件流式转过去
+    pub fn run(&self) -> std::io::Result<()> {
+        let mut reader = std::fs::File::open(&self.input)?;
+        let mut writer = std::io::BufWriter::new(std::fs::File::create(&self.output)?);
+        transcode_reader_to_writer(&mut reader, &mut writer, &self.options)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    /* "你好，世界。编码转换测试文本。" 的 GBK 字节 */
+    const GBK_SAMPLE: &[u8] = &[
+        0xC4, 0xE3, 0xBA, 0xC3, 0xA3, 0xAC, 0xCA, 0xC0, 0xBD, 0xE7, 0xA1, 0xA3, 0xB1, 0xE0, 0xC2,
+        0xEB, 0xD7, 0xAA, 0xBB, 0xBB, 0xB2, 0xE2, 0xCA, 0xD4, 0xCE, 0xC4, 0xB1, 0xBE, 0xA1, 0xA3,
+    ];
+    const SAMPLE_TEXT: &str = "你好，世界。编码转换测试文本。";
+
+    #[test]
+    fn detect_prefers_bom() {
+        assert_eq!(detect(b"\xFF\xFEa\x00b\x00"), UTF_16LE);
+        assert_eq!(detect(b"\xFE\xFF\x00a\x00b"), UTF_16BE);
+        assert_eq!(detect(b"\xEF\xBB\xBFhello"), UTF_8);
+    }
+
+    #[test]
+    fn detect_valid_utf8_and_gbk() {
+        assert_eq!(detect("纯 UTF-8 文本".as_bytes()), UTF_8);
+        assert_eq!(detect(GBK_SAMPLE), GBK);
+    }
+
+    #[test]
+    fn detect_truncated_utf8_tail_still_utf8() {
+        let mut data = "完整的 UTF-8".as_bytes().to_vec();
+        data.pop(); /* 切在多字节序列中间 */
+        assert_eq!(detect(&data), UTF_8);
+    }
+
+    #[test]
+    fn strip_bom_only_when_matching() {
+        assert_eq!(strip_bom(b"\xEF\xBB\xBFabc", UTF_8), b"abc");
+        assert_eq!(strip_bom(b"abc", UTF_8), b"abc");
+        assert_eq!(strip_bom(b"\xEF\xBB\xBFabc", GBK), b"\xEF\xBB\xBFabc");
+    }
+
+    #[test]
+    fn plausibility_prefers_clean_text() {
+        assert!(plausibility_score("正常的中文句子。") > plausibility_score("ÇàÉ¶ÓÐÎÊÌâ"));
+    }
+
+    #[test]
+    fn stream_gbk_to_utf8() {
+        let mut reader = Cursor::new(GBK_SAMPLE);
+        let mut out = Vec::new();
+        let opts = TranscodeOptions {
+            from: GBK,
+            to: UTF_8,
+            write_bom: false,
+        };
+        transcode_reader_to_writer(&mut reader, &mut out, &opts).unwrap();
+        assert_eq!(out, SAMPLE_TEXT.as_bytes());
+    }
+
+    #[test]
+    fn stream_writes_bom_when_asked() {
+        let mut reader = Cursor::new("abc".as_bytes());
+        let mut out = Vec::new();
+        let opts = TranscodeOptions {
+            write_bom: true,
+            ..Default::default()
+        };
+        transcode_reader_to_writer(&mut reader, &mut out, &opts).unwrap();
+        assert_eq!(out, b"\xEF\xBB\xBFabc");
+    }
+
+    #[test]
+    fn stream_handles_input_larger_than_buffer() {
+        let text = "混合 content 一二三四五 ABC ".repeat(4096);
+        let gbk = GBK.encode(&text).0.into_owned();
+        let mut reader = Cursor::new(gbk);
+        let mut out = Vec::new();
+        let opts = TranscodeOptions {
+            from: GBK,
+            to: UTF_8,
+            write_bom: false,
+        };
+        transcode_reader_to_writer(&mut reader, &mut out, &opts).unwrap();
+        assert_eq!(out, text.as_bytes());
+    }
+
+    #[test]
+    fn batch_job_converts_file() {
+        let dir = std::env::temp_dir();
+        let input = dir.join("encodeconverter_core_test_in.txt");
+        let output = dir.join("encodeconverter_core_test_out.txt");
+        std::fs::write(&input, GBK_SAMPLE).unwrap();
+        let job = BatchJob {
+            input: input.clone(),
+            output: output.clone(),
+            options: TranscodeOptions {
+                from: GBK,
+                to: UTF_8,
+                write_bom: false,
+            },
+        };
+        job.run().unwrap();
+        assert_eq!(std::fs::read(&output).unwrap(), SAMPLE_TEXT.as_bytes());
+        std::fs::remove_file(&input).ok();
+        std::fs::remove_file(&output).ok();
+    }
+}
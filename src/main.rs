@@ -5,11 +5,20 @@ use eframe::{
     egui::{self, ViewportBuilder},
     icon_data::from_png_bytes,
 };
+use encodeconverter_core::{
+    COMMON_ENCODINGS as REPAIR_ENCODINGS, bom_of, plausibility_score, strip_bom,
+};
 use encoding_rs::*;
 use std::{
-    path::PathBuf,
-    sync::{Arc, mpsc},
+    net::{TcpListener, TcpStream},
+    path::{Path, PathBuf},
+    sync::{
+        Arc,
+        atomic::{AtomicU64, AtomicUsize, Ordering},
+        mpsc,
+    },
     thread,
+    time::{Duration, Instant},
 };
 
 static FONT: &[u8] = include_bytes!("../font.ttf");
@@ -58,249 +67,7846 @@ static ENCODINGS: &[EncodingItem] = &[
     (KOI8_R, "KOI8-R"),
     (KOI8_U, "KOI8-U"),
     (IBM866, "IBM866"),
+    /* encoding_rs 没有 UTF-32, 这两项按标签走手工编解码,
+    第一列只是占位, 不会被用到 */
+    (UTF_16LE, "UTF-32LE"),
+    (UTF_16BE, "UTF-32BE"),
 ];
 
 /* ======================= 语言 ======================= */
+/*
+    词条按语言分表存放, 加新语言就是加一张表挂进 lang_table;
+    缺的词条先退回英文, 再退回键名本身, 翻译可以慢慢补。
+    第一次启动没有存过语言时按系统区域设置挑一种
+*/
 #[derive(Clone, Copy, PartialEq)]
 enum Language {
     Zh,
+    ZhTw,
     En,
+    Ja,
+    Ko,
+    Ru,
 }
 
-fn t(key: &str, lang: Language) -> &str {
+impl Language {
+    const ALL: [Language; 6] = [
+        Language::Zh,
+        Language::ZhTw,
+        Language::En,
+        Language::Ja,
+        Language::Ko,
+        Language::Ru,
+    ];
+
+    /* 持久化用的标签 */
+    fn tag(self) -> &'static str {
+        match self {
+            Language::Zh => "zh",
+            Language::ZhTw => "zh-tw",
+            Language::En => "en",
+            Language::Ja => "ja",
+            Language::Ko => "ko",
+            Language::Ru => "ru",
+        }
+    }
+
+    fn from_tag(tag: &str) -> Option<Language> {
+        Language::ALL.into_iter().find(|l| l.tag() == tag)
+    }
+
+    /* 语言选择框里显示本族写法 */
+    fn native_name(self) -> &'static str {
+        match self {
+            Language::Zh => "简体中文",
+            Language::ZhTw => "繁體中文",
+            Language::En => "English",
+            Language::Ja => "日本語",
+            Language::Ko => "한국어",
+            Language::Ru => "Русский",
+        }
+    }
+}
+
+/* 系统区域设置 -> 界面语言, 认不出来用英文 */
+fn locale_to_language(locale: &str) -> Language {
+    let l = locale.to_ascii_lowercase();
+    if l.starts_with("zh") {
+        if ["tw", "hk", "mo", "hant"].iter().any(|r| l.contains(r)) {
+            Language::ZhTw
+        } else {
+            Language::Zh
+        }
+    } else if l.starts_with("ja") {
+        Language::Ja
+    } else if l.starts_with("ko") {
+        Language::Ko
+    } else if l.starts_with("ru") {
+        Language::Ru
+    } else {
+        Language::En
+    }
+}
+
+fn detect_os_language() -> Language {
+    ["LC_ALL", "LC_MESSAGES", "LANG"]
+        .iter()
+        .filter_map(|v| std::env::var(v).ok())
+        .find(|v| !v.is_empty())
+        .map(|v| locale_to_language(&v))
+        .unwrap_or(Language::En)
+}
+
+type Strings = &'static [(&'static str, &'static str)];
+
+static ZH: Strings = &[
+    ("text", "文本转码"),
+    ("file", "文件转码"),
+    ("from", "来源编码"),
+    ("to", "目标编码"),
+    ("start", "开始转码"),
+    ("input", "输入文本"),
+    ("output", "输出结果"),
+    ("select_input", "选择输入文件"),
+    ("select_output", "选择输出文件"),
+    ("working", "正在转码..."),
+    ("idle", "暂无状态"),
+    ("strip_bom", "去除源文件 BOM"),
+    ("write_bom", "写入目标 BOM"),
+    ("conflict", "同名文件"),
+    ("overwrite", "覆盖"),
+    ("skip", "跳过"),
+    ("rename", "重命名"),
+    ("ask", "询问"),
+    ("exists", "输出文件已存在:"),
+    ("skipped", "已跳过"),
+    ("eol", "换行符"),
+    ("keep", "保留"),
+    ("dir", "目录转码"),
+    ("select_dir", "选择根目录"),
+    ("select_outdir", "选择输出目录"),
+    ("pattern", "文件名过滤"),
+    ("in_place", "原地转换"),
+    ("results", "转换结果"),
+    ("backup", "先备份 (.bak)"),
+    ("preview", "字节预览"),
+    ("in_escaped", "输入为转义字节"),
+    ("in_base64", "输入为 Base64"),
+    ("as_html", "按 HTML 处理"),
+    ("looks_hex", "像是十六进制字节 — 先解码?"),
+    ("looks_pct", "像是百分号编码 — 先解码?"),
+    ("looks_b64", "像是 Base64 — 先解码?"),
+    ("out_repr", "输出形式"),
+    ("repr_text", "文本"),
+    ("theme", "主题"),
+    ("theme_default", "默认"),
+    ("theme_hc", "高对比度"),
+    ("theme_large", "大字体"),
+    ("theme_system", "跟随系统"),
+    ("theme_light", "浅色"),
+    ("theme_dark", "深色"),
+    ("font_size", "字号"),
+    ("recent", "最近任务"),
+    ("repeat", "重新执行"),
+    ("open_folder", "打开所在目录"),
+    ("open_file", "打开输出文件"),
+    ("auto_open", "转完自动打开所在目录"),
+    ("incremental", "增量 (跳过没改动的)"),
+    ("unchanged", "未改动, 跳过"),
+    ("regressed", "回退到旧编码"),
+    ("from_url", "从 URL"),
+    ("fetch", "抓取"),
+    ("fetched", "已下载:"),
+    ("fallback", "备选编码链"),
+    (
+        "fallback_hint",
+        "按顺序逐个试, 采用第一个解不出替换符的; 留空只用上面选的来源编码",
+    ),
+    ("unmappable", "无法映射"),
+    ("eta", "预计剩余"),
+    ("apply_all", "应用到全部"),
+    ("compare", "转换前后对比 (前 200 行)"),
+    ("commit", "确认写入"),
+    ("cancel", "取消"),
+    ("lost_chars", "个字符无法在目标编码中表示,将被替换"),
+    ("lost_samples", "丢失示例"),
+    ("tab_before", "转换前"),
+    ("tab_after", "转换后"),
+    ("zip_pwd", "压缩包密码 (可留空)"),
+    ("sidecar", "写 .meta.json 旁记"),
+    ("revert_meta", "按旁记回转"),
+    ("revert_inexact", "与原文件不完全一致"),
+    ("verify", "写后校验"),
+    ("verify_fail", "回读校验不一致"),
+    ("binary", "二进制文件"),
+    ("force", "强制转换"),
+    ("skipped_binary", "像是二进制, 已跳过"),
+    ("binary_warn", "文件像是二进制, 硬转会毁数据:"),
+    ("dismiss_all", "全部关闭"),
+    ("pair_defaults", "已按编码对套用默认项"),
+    ("sub_suffix", "字幕语言后缀 (如 zh, 可留空)"),
+    ("view_only", "仅查看 (不写盘)"),
+    ("viewer", "只读查看"),
+    ("sandbox", "只允许写入信任目录"),
+    ("trusted_dirs", "信任目录"),
+    ("add_trusted", "添加信任目录"),
+    ("sandbox_blocked", "目标不在信任目录内:"),
+    ("trust_dir", "信任此目录"),
+    ("allow_once", "本次运行允许"),
+    ("preset", "预设"),
+    ("preset_pick", "选择预设"),
+    ("preset_name", "预设名"),
+    ("save_preset", "保存预设"),
+    ("suffix_out", "输出名带编码后缀"),
+    ("name_tpl", "命名模板 (可用 {stem} {ext} {relpath})"),
+    ("same_path", "输出就是输入文件本身, 将原地覆盖:"),
+    ("pair_subs", "只转与视频同名的字幕"),
+    ("mixed_eol", "检测到混合换行符"),
+    ("fix_eol", "统一为多数派"),
+    ("err_io", "读写失败:"),
+    ("err_perm", "没有权限:"),
+    ("err_decode", "来源解码有错:"),
+    ("err_unmap", "个字符映射不到目标编码"),
+    ("bytes", "字节"),
+    ("done_n", "完成:"),
+    ("analyzed_n", "已分析:"),
+    ("files_n", "个文件"),
+    ("log", "日志"),
+    ("log_all", "全部"),
+    ("log_warn", "警告+"),
+    ("log_err", "仅错误"),
+    ("copy_log", "复制日志"),
+    ("menu_file", "文件"),
+    ("open", "打开… (Ctrl+O)"),
+    ("save_out", "保存输出…"),
+    ("quit", "退出"),
+    ("menu_edit", "编辑"),
+    ("menu_mode", "模式"),
+    ("swap", "交换编码 (Ctrl+L)"),
+    ("char_map", "字符表"),
+    ("char_search", "搜索名称或码点 (U+XXXX)"),
+    ("rep_entities", "HTML 实体解码"),
+    ("rep_eol", "换行统一"),
+    ("rep_replaced", "无法编码被替换"),
+    ("names", "文件名转码"),
+    ("preview_names", "预览改名"),
+    ("apply_names", "执行改名"),
+    ("undo_names", "撤销上次改名"),
+    ("no_renames", "没有需要转换的文件名"),
+    ("repair", "乱码修复"),
+    ("playground", "对照解码"),
+    ("play_input", "字节输入 (十六进制 / \\x / %)"),
+    ("garbled", "粘贴乱码文本"),
+    ("analyze", "分析"),
+    ("candidates", "候选修复"),
+    ("use_candidate", "采用"),
+    ("no_candidates", "没有找到更可读的解码方式"),
+    ("paste_clip", "粘贴剪贴板"),
+    ("copy_out", "复制结果"),
+    ("convert_clip", "转换剪贴板"),
+    ("clip_done", "剪贴板已转换"),
+    ("clip_empty", "剪贴板没有文本"),
+    ("paste_files", "粘贴文件"),
+    ("add_dir", "添加目录"),
+    ("add_files", "添加文件"),
+    ("clear_roots", "清空列表"),
+    ("analyze_only", "仅分析"),
+    ("analysis", "分析结果"),
+    ("export_csv", "导出 CSV"),
+    ("col_file", "文件"),
+    ("col_enc", "编码"),
+    ("col_bom", "BOM"),
+    ("col_eol", "换行"),
+    ("col_lossy", "有损"),
+    ("col_snippet", "首行"),
+    ("yes", "是"),
+    ("no", "否"),
+    ("clip_no_files", "剪贴板没有文件"),
+];
+
+static ZH_TW: Strings = &[
+    ("text", "文字轉碼"),
+    ("file", "檔案轉碼"),
+    ("from", "來源編碼"),
+    ("to", "目標編碼"),
+    ("start", "開始轉碼"),
+    ("input", "輸入文字"),
+    ("output", "輸出結果"),
+    ("select_input", "選擇輸入檔案"),
+    ("select_output", "選擇輸出檔案"),
+    ("working", "正在轉碼..."),
+    ("idle", "暫無狀態"),
+    ("strip_bom", "去除來源檔 BOM"),
+    ("write_bom", "寫入目標 BOM"),
+    ("conflict", "同名檔案"),
+    ("overwrite", "覆寫"),
+    ("skip", "跳過"),
+    ("rename", "重新命名"),
+    ("ask", "詢問"),
+    ("exists", "輸出檔案已存在:"),
+    ("skipped", "已跳過"),
+    ("eol", "換行符"),
+    ("keep", "保留"),
+    ("dir", "目錄轉碼"),
+    ("select_dir", "選擇根目錄"),
+    ("select_outdir", "選擇輸出目錄"),
+    ("pattern", "檔名過濾"),
+    ("in_place", "原地轉換"),
+    ("results", "轉換結果"),
+    ("backup", "先備份 (.bak)"),
+    ("preview", "位元組預覽"),
+    ("in_escaped", "輸入為轉義位元組"),
+    ("in_base64", "輸入為 Base64"),
+    ("as_html", "按 HTML 處理"),
+    ("looks_hex", "像是十六進位位元組 — 先解碼?"),
+    ("looks_pct", "像是百分號編碼 — 先解碼?"),
+    ("looks_b64", "像是 Base64 — 先解碼?"),
+    ("out_repr", "輸出形式"),
+    ("repr_text", "文字"),
+    ("theme", "主題"),
+    ("theme_default", "預設"),
+    ("theme_hc", "高對比"),
+    ("theme_large", "大字體"),
+    ("theme_system", "跟隨系統"),
+    ("theme_light", "淺色"),
+    ("theme_dark", "深色"),
+    ("font_size", "字號"),
+    ("recent", "最近任務"),
+    ("repeat", "重新執行"),
+    ("open_folder", "開啟所在目錄"),
+    ("open_file", "開啟輸出檔案"),
+    ("auto_open", "轉完自動開啟所在目錄"),
+    ("incremental", "增量 (跳過沒改動的)"),
+    ("unchanged", "未改動, 跳過"),
+    ("regressed", "回退到舊編碼"),
+    ("from_url", "從 URL"),
+    ("fetch", "抓取"),
+    ("fetched", "已下載:"),
+    ("fallback", "備選編碼鏈"),
+    (
+        "fallback_hint",
+        "按順序逐個試, 採用第一個解不出替換符的; 留空只用上面選的來源編碼",
+    ),
+    ("unmappable", "無法映射"),
+    ("eta", "預計剩餘"),
+    ("apply_all", "套用到全部"),
+    ("compare", "轉換前後對比 (前 200 行)"),
+    ("commit", "確認寫入"),
+    ("cancel", "取消"),
+    ("lost_chars", "個字元無法在目標編碼中表示,將被取代"),
+    ("lost_samples", "遺失示例"),
+    ("tab_before", "轉換前"),
+    ("tab_after", "轉換後"),
+    ("zip_pwd", "壓縮檔密碼 (可留空)"),
+    ("sidecar", "寫 .meta.json 旁記"),
+    ("revert_meta", "按旁記回轉"),
+    ("revert_inexact", "與原檔案不完全一致"),
+    ("verify", "寫後校驗"),
+    ("verify_fail", "回讀校驗不一致"),
+    ("binary", "二進位檔案"),
+    ("force", "強制轉換"),
+    ("skipped_binary", "像是二進位, 已跳過"),
+    ("binary_warn", "檔案像是二進位, 硬轉會毀資料:"),
+    ("dismiss_all", "全部關閉"),
+    ("pair_defaults", "已按編碼對套用預設項"),
+    ("sub_suffix", "字幕語言後綴 (如 zh, 可留空)"),
+    ("view_only", "僅檢視 (不寫入)"),
+    ("viewer", "唯讀檢視"),
+    ("sandbox", "只允許寫入信任目錄"),
+    ("trusted_dirs", "信任目錄"),
+    ("add_trusted", "新增信任目錄"),
+    ("sandbox_blocked", "目標不在信任目錄內:"),
+    ("trust_dir", "信任此目錄"),
+    ("allow_once", "本次執行允許"),
+    ("preset", "預設組合"),
+    ("preset_pick", "選擇預設組合"),
+    ("preset_name", "預設組合名"),
+    ("save_preset", "儲存預設組合"),
+    ("suffix_out", "輸出名帶編碼後綴"),
+    ("name_tpl", "命名範本 (可用 {stem} {ext} {relpath})"),
+    ("same_path", "輸出就是輸入檔案本身, 將原地覆寫:"),
+    ("pair_subs", "只轉與影片同名的字幕"),
+    ("mixed_eol", "偵測到混合換行符"),
+    ("fix_eol", "統一為多數派"),
+    ("err_io", "讀寫失敗:"),
+    ("err_perm", "沒有權限:"),
+    ("err_decode", "來源解碼有錯:"),
+    ("err_unmap", "個字元對應不到目標編碼"),
+    ("bytes", "位元組"),
+    ("done_n", "完成:"),
+    ("analyzed_n", "已分析:"),
+    ("files_n", "個檔案"),
+    ("log", "日誌"),
+    ("log_all", "全部"),
+    ("log_warn", "警告+"),
+    ("log_err", "僅錯誤"),
+    ("copy_log", "複製日誌"),
+    ("menu_file", "檔案"),
+    ("open", "開啟… (Ctrl+O)"),
+    ("save_out", "儲存輸出…"),
+    ("quit", "結束"),
+    ("menu_edit", "編輯"),
+    ("menu_mode", "模式"),
+    ("swap", "交換編碼 (Ctrl+L)"),
+    ("char_map", "字元表"),
+    ("char_search", "搜尋名稱或碼位 (U+XXXX)"),
+    ("rep_entities", "HTML 實體解碼"),
+    ("rep_eol", "換行統一"),
+    ("rep_replaced", "無法編碼被取代"),
+    ("names", "檔名轉碼"),
+    ("preview_names", "預覽改名"),
+    ("apply_names", "執行改名"),
+    ("undo_names", "復原上次改名"),
+    ("no_renames", "沒有需要轉換的檔名"),
+    ("repair", "亂碼修復"),
+    ("playground", "對照解碼"),
+    ("play_input", "位元組輸入 (十六進位 / \\x / %)"),
+    ("garbled", "貼上亂碼文字"),
+    ("analyze", "分析"),
+    ("candidates", "候選修復"),
+    ("use_candidate", "採用"),
+    ("no_candidates", "沒有找到更可讀的解碼方式"),
+    ("paste_clip", "貼上剪貼簿"),
+    ("copy_out", "複製結果"),
+    ("convert_clip", "轉換剪貼簿"),
+    ("clip_done", "剪貼簿已轉換"),
+    ("clip_empty", "剪貼簿沒有文字"),
+    ("paste_files", "貼上檔案"),
+    ("add_dir", "新增目錄"),
+    ("add_files", "新增檔案"),
+    ("clear_roots", "清空清單"),
+    ("analyze_only", "僅分析"),
+    ("analysis", "分析結果"),
+    ("export_csv", "匯出 CSV"),
+    ("col_file", "檔案"),
+    ("col_enc", "編碼"),
+    ("col_bom", "BOM"),
+    ("col_eol", "換行"),
+    ("col_lossy", "有損"),
+    ("col_snippet", "首行"),
+    ("yes", "是"),
+    ("no", "否"),
+    ("clip_no_files", "剪貼簿沒有檔案"),
+];
+
+static EN: Strings = &[
+    ("text", "Text"),
+    ("file", "File"),
+    ("from", "From"),
+    ("to", "To"),
+    ("start", "Start"),
+    ("input", "Input Text"),
+    ("output", "Output"),
+    ("select_input", "Select Input File"),
+    ("select_output", "Select Output File"),
+    ("working", "Working..."),
+    ("idle", "Idle"),
+    ("strip_bom", "Strip source BOM"),
+    ("write_bom", "Write target BOM"),
+    ("conflict", "On conflict"),
+    ("overwrite", "Overwrite"),
+    ("skip", "Skip"),
+    ("rename", "Rename"),
+    ("ask", "Ask"),
+    ("exists", "Output already exists:"),
+    ("skipped", "Skipped"),
+    ("eol", "Line endings"),
+    ("keep", "Keep"),
+    ("dir", "Directory"),
+    ("select_dir", "Select Root Folder"),
+    ("select_outdir", "Select Output Folder"),
+    ("pattern", "Pattern"),
+    ("in_place", "In place"),
+    ("results", "Results"),
+    ("backup", "Backup first (.bak)"),
+    ("preview", "Byte preview"),
+    ("in_escaped", "Input is escaped bytes"),
+    ("in_base64", "Input is Base64"),
+    ("as_html", "Treat as HTML"),
+    ("looks_hex", "Looks like hex bytes — decode first?"),
+    ("looks_pct", "Looks like percent-encoding — decode first?"),
+    ("looks_b64", "Looks like Base64 — decode first?"),
+    ("out_repr", "Output as"),
+    ("repr_text", "Text"),
+    ("theme", "Theme"),
+    ("theme_default", "Default"),
+    ("theme_hc", "High contrast"),
+    ("theme_large", "Large text"),
+    ("theme_system", "Follow system"),
+    ("theme_light", "Light"),
+    ("theme_dark", "Dark"),
+    ("font_size", "Font size"),
+    ("recent", "Recent"),
+    ("repeat", "Repeat"),
+    ("open_folder", "Open folder"),
+    ("open_file", "Open file"),
+    ("auto_open", "Auto-open folder when done"),
+    ("incremental", "Incremental (skip unchanged)"),
+    ("unchanged", "unchanged, skipped"),
+    ("regressed", "regressed to legacy encoding"),
+    ("from_url", "From URL"),
+    ("fetch", "Fetch"),
+    ("fetched", "Downloaded:"),
+    ("fallback", "Fallback encoding chain"),
+    (
+        "fallback_hint",
+        "Tried in order; the first that decodes without replacement characters wins. Empty = use the source encoding above",
+    ),
+    ("unmappable", "unmappable"),
+    ("eta", "ETA"),
+    ("apply_all", "Apply to all"),
+    ("compare", "Before / after (first 200 lines)"),
+    ("commit", "Write output"),
+    ("cancel", "Cancel"),
+    (
+        "lost_chars",
+        "character(s) cannot be represented in the target encoding and will be replaced",
+    ),
+    ("lost_samples", "Samples"),
+    ("tab_before", "Before"),
+    ("tab_after", "After"),
+    ("zip_pwd", "Archive password (optional)"),
+    ("sidecar", "Write .meta.json sidecar"),
+    ("revert_meta", "Revert using metadata"),
+    ("revert_inexact", "differs from original"),
+    ("verify", "Verify after write"),
+    ("verify_fail", "read-back verify mismatch"),
+    ("binary", "Binary files"),
+    ("force", "Force"),
+    ("skipped_binary", "looks binary, skipped"),
+    ("binary_warn", "Looks binary — converting may destroy it:"),
+    ("dismiss_all", "Dismiss all"),
+    ("pair_defaults", "pair defaults applied"),
+    ("sub_suffix", "Subtitle language suffix (e.g. zh, optional)"),
+    ("view_only", "View only (no write)"),
+    ("viewer", "Read-only view"),
+    ("sandbox", "Only write inside trusted folders"),
+    ("trusted_dirs", "Trusted folders"),
+    ("add_trusted", "Add trusted folder"),
+    ("sandbox_blocked", "Target is outside trusted folders:"),
+    ("trust_dir", "Trust this folder"),
+    ("allow_once", "Allow for this run"),
+    ("preset", "Preset"),
+    ("preset_pick", "Apply preset…"),
+    ("preset_name", "Preset name"),
+    ("save_preset", "Save preset"),
+    ("suffix_out", "Suffix output with encoding"),
+    ("name_tpl", "Name template ({stem} {ext} {relpath})"),
+    (
+        "same_path",
+        "Output is the input file itself and will be overwritten in place:",
+    ),
+    ("pair_subs", "Only subtitles matching a video"),
+    ("mixed_eol", "Mixed line endings detected"),
+    ("fix_eol", "Normalize to majority"),
+    ("err_io", "I/O error:"),
+    ("err_perm", "Permission denied:"),
+    ("err_decode", "Decode errors in"),
+    ("err_unmap", "chars unmappable in target encoding"),
+    ("bytes", "bytes"),
+    ("done_n", "Done:"),
+    ("analyzed_n", "Analyzed:"),
+    ("files_n", "files"),
+    ("log", "Log"),
+    ("log_all", "All"),
+    ("log_warn", "Warnings+"),
+    ("log_err", "Errors only"),
+    ("copy_log", "Copy log"),
+    ("menu_file", "File"),
+    ("open", "Open… (Ctrl+O)"),
+    ("save_out", "Save output…"),
+    ("quit", "Quit"),
+    ("menu_edit", "Edit"),
+    ("menu_mode", "Mode"),
+    ("swap", "Swap encodings (Ctrl+L)"),
+    ("char_map", "Character map"),
+    ("char_search", "Search name or code point (U+XXXX)"),
+    ("rep_entities", "HTML entities decoded"),
+    ("rep_eol", "line endings normalized"),
+    ("rep_replaced", "unencodable replaced"),
+    ("names", "Filenames"),
+    ("preview_names", "Preview renames"),
+    ("apply_names", "Apply renames"),
+    ("undo_names", "Undo last renames"),
+    ("no_renames", "No filenames need converting"),
+    ("repair", "Fix garbled"),
+    ("playground", "Playground"),
+    ("play_input", "Byte input (hex / \\x / %)"),
+    ("garbled", "Paste garbled text"),
+    ("analyze", "Analyze"),
+    ("candidates", "Candidates"),
+    ("use_candidate", "Use"),
+    ("no_candidates", "No more readable decoding found"),
+    ("paste_clip", "Paste clipboard"),
+    ("copy_out", "Copy output"),
+    ("convert_clip", "Convert clipboard"),
+    ("clip_done", "Clipboard converted"),
+    ("clip_empty", "No text on clipboard"),
+    ("paste_files", "Paste files"),
+    ("add_dir", "Add folder"),
+    ("add_files", "Add files"),
+    ("clear_roots", "Clear list"),
+    ("analyze_only", "Analyze only"),
+    ("analysis", "Analysis"),
+    ("export_csv", "Export CSV"),
+    ("col_file", "File"),
+    ("col_enc", "Encoding"),
+    ("col_bom", "BOM"),
+    ("col_eol", "EOL"),
+    ("col_lossy", "Lossy"),
+    ("col_snippet", "First line"),
+    ("yes", "Yes"),
+    ("no", "No"),
+    ("clip_no_files", "No files on clipboard"),
+];
+
+static JA: Strings = &[
+    ("text", "テキスト変換"),
+    ("file", "ファイル変換"),
+    ("from", "変換元"),
+    ("to", "変換先"),
+    ("start", "開始"),
+    ("input", "入力テキスト"),
+    ("output", "出力結果"),
+    ("select_input", "入力ファイルを選択"),
+    ("select_output", "出力ファイルを選択"),
+    ("working", "変換中..."),
+    ("idle", "待機中"),
+    ("strip_bom", "元の BOM を除去"),
+    ("write_bom", "出力に BOM を付加"),
+    ("conflict", "同名ファイル"),
+    ("overwrite", "上書き"),
+    ("skip", "スキップ"),
+    ("rename", "名前を変更"),
+    ("ask", "確認する"),
+    ("exists", "出力ファイルは既に存在します:"),
+    ("skipped", "スキップ済み"),
+    ("eol", "改行コード"),
+    ("keep", "そのまま"),
+    ("dir", "フォルダ変換"),
+    ("select_dir", "ルートフォルダを選択"),
+    ("select_outdir", "出力フォルダを選択"),
+    ("pattern", "ファイル名フィルタ"),
+    ("in_place", "その場で変換"),
+    ("results", "変換結果"),
+    ("backup", "先にバックアップ (.bak)"),
+    ("preview", "バイトプレビュー"),
+    ("in_escaped", "入力はエスケープ済みバイト"),
+    ("in_base64", "入力は Base64"),
+    ("as_html", "HTML として処理"),
+    ("looks_hex", "16進バイトのようです — 先にデコード?"),
+    (
+        "looks_pct",
+        "パーセントエンコードのようです — 先にデコード?",
+    ),
+    ("looks_b64", "Base64 のようです — 先にデコード?"),
+    ("out_repr", "出力形式"),
+    ("repr_text", "テキスト"),
+    ("theme", "テーマ"),
+    ("theme_default", "標準"),
+    ("theme_hc", "ハイコントラスト"),
+    ("theme_large", "大きい文字"),
+    ("theme_system", "システムに従う"),
+    ("theme_light", "ライト"),
+    ("theme_dark", "ダーク"),
+    ("font_size", "文字サイズ"),
+    ("recent", "最近のタスク"),
+    ("repeat", "再実行"),
+    ("open_folder", "フォルダを開く"),
+    ("open_file", "ファイルを開く"),
+    ("auto_open", "完了後フォルダを自動で開く"),
+    ("incremental", "増分 (未変更をスキップ)"),
+    ("unchanged", "変更なし, スキップ"),
+    ("regressed", "旧エンコーディングに戻っています"),
+    ("from_url", "URL から"),
+    ("fetch", "取得"),
+    ("fetched", "ダウンロード完了:"),
+    ("fallback", "フォールバックエンコーディング"),
+    (
+        "fallback_hint",
+        "順に試して置換文字が出ない最初のものを採用。空なら上の変換元をそのまま使う",
+    ),
+    ("unmappable", "対応なし"),
+    ("eta", "残り"),
+    ("apply_all", "すべてに適用"),
+    ("compare", "変換前後の比較 (先頭 200 行)"),
+    ("commit", "書き込む"),
+    ("cancel", "キャンセル"),
+    (
+        "lost_chars",
+        "文字が変換先エンコーディングで表現できず置換されます",
+    ),
+    ("lost_samples", "例"),
+    ("tab_before", "変換前"),
+    ("tab_after", "変換後"),
+    ("zip_pwd", "アーカイブのパスワード (省略可)"),
+    ("sidecar", ".meta.json サイドカーを書く"),
+    ("revert_meta", "サイドカーで元に戻す"),
+    ("revert_inexact", "元ファイルと完全一致せず"),
+    ("verify", "書き込み後に検証"),
+    ("verify_fail", "書き込み後の検証で不一致"),
+    ("binary", "バイナリファイル"),
+    ("force", "強制変換"),
+    ("skipped_binary", "バイナリとみなしてスキップ"),
+    (
+        "binary_warn",
+        "バイナリのようです。変換するとデータが壊れる恐れ:",
+    ),
+    ("dismiss_all", "すべて閉じる"),
+    ("pair_defaults", "エンコーディングペアの既定を適用しました"),
+    ("sub_suffix", "字幕の言語サフィックス (例 zh, 省略可)"),
+    ("view_only", "表示のみ (書き込まない)"),
+    ("viewer", "読み取り専用ビュー"),
+    ("sandbox", "信頼フォルダ内のみ書き込み許可"),
+    ("trusted_dirs", "信頼フォルダ"),
+    ("add_trusted", "信頼フォルダを追加"),
+    ("sandbox_blocked", "出力先が信頼フォルダの外です:"),
+    ("trust_dir", "このフォルダを信頼"),
+    ("allow_once", "今回のみ許可"),
+    ("preset", "プリセット"),
+    ("preset_pick", "プリセットを適用…"),
+    ("preset_name", "プリセット名"),
+    ("save_preset", "プリセットを保存"),
+    ("suffix_out", "出力名にエンコーディングを付加"),
+    ("name_tpl", "命名テンプレート ({stem} {ext} {relpath})"),
+    (
+        "same_path",
+        "出力先が入力ファイル自身のため、その場で上書きされます:",
+    ),
+    ("pair_subs", "動画と同名の字幕のみ"),
+    ("mixed_eol", "改行コードが混在しています"),
+    ("fix_eol", "多数派に統一"),
+    ("err_io", "入出力エラー:"),
+    ("err_perm", "権限がありません:"),
+    ("err_decode", "デコードエラー:"),
+    ("err_unmap", "文字が変換先エンコーディングに対応しません"),
+    ("bytes", "バイト"),
+    ("done_n", "完了:"),
+    ("analyzed_n", "解析済み:"),
+    ("files_n", "ファイル"),
+    ("log", "ログ"),
+    ("log_all", "すべて"),
+    ("log_warn", "警告以上"),
+    ("log_err", "エラーのみ"),
+    ("copy_log", "ログをコピー"),
+    ("menu_file", "ファイル"),
+    ("open", "開く… (Ctrl+O)"),
+    ("save_out", "出力を保存…"),
+    ("quit", "終了"),
+    ("menu_edit", "編集"),
+    ("menu_mode", "モード"),
+    ("swap", "エンコーディングを入れ替え (Ctrl+L)"),
+    ("char_map", "文字マップ"),
+    ("char_search", "名前またはコードポイントで検索 (U+XXXX)"),
+    ("rep_entities", "HTML 実体参照をデコード"),
+    ("rep_eol", "改行を統一"),
+    ("rep_replaced", "変換不能文字を置換"),
+    ("names", "ファイル名変換"),
+    ("preview_names", "改名をプレビュー"),
+    ("apply_names", "改名を実行"),
+    ("undo_names", "改名を元に戻す"),
+    ("no_renames", "変換が必要なファイル名はありません"),
+    ("repair", "文字化け修復"),
+    ("playground", "デコード比較"),
+    ("play_input", "バイト入力 (16進 / \\x / %)"),
+    ("garbled", "文字化けテキストを貼り付け"),
+    ("analyze", "解析"),
+    ("candidates", "修復候補"),
+    ("use_candidate", "採用"),
+    ("no_candidates", "より読みやすいデコードは見つかりません"),
+    ("paste_clip", "クリップボードを貼り付け"),
+    ("copy_out", "結果をコピー"),
+    ("convert_clip", "クリップボードを変換"),
+    ("clip_done", "クリップボードを変換しました"),
+    ("clip_empty", "クリップボードにテキストがありません"),
+    ("paste_files", "ファイルを貼り付け"),
+    ("add_dir", "フォルダを追加"),
+    ("add_files", "ファイルを追加"),
+    ("clear_roots", "リストをクリア"),
+    ("analyze_only", "解析のみ"),
+    ("analysis", "解析結果"),
+    ("export_csv", "CSV を書き出し"),
+    ("col_file", "ファイル"),
+    ("col_enc", "エンコーディング"),
+    ("col_bom", "BOM"),
+    ("col_eol", "改行"),
+    ("col_lossy", "損失あり"),
+    ("col_snippet", "先頭行"),
+    ("yes", "はい"),
+    ("no", "いいえ"),
+    ("clip_no_files", "クリップボードにファイルがありません"),
+];
+
+static KO: Strings = &[
+    ("text", "텍스트 변환"),
+    ("file", "파일 변환"),
+    ("from", "원본 인코딩"),
+    ("to", "대상 인코딩"),
+    ("start", "시작"),
+    ("input", "입력 텍스트"),
+    ("output", "출력 결과"),
+    ("select_input", "입력 파일 선택"),
+    ("select_output", "출력 파일 선택"),
+    ("working", "변환 중..."),
+    ("idle", "대기 중"),
+    ("strip_bom", "원본 BOM 제거"),
+    ("write_bom", "대상에 BOM 기록"),
+    ("conflict", "같은 이름 파일"),
+    ("overwrite", "덮어쓰기"),
+    ("skip", "건너뛰기"),
+    ("rename", "이름 바꾸기"),
+    ("ask", "확인"),
+    ("exists", "출력 파일이 이미 있습니다:"),
+    ("skipped", "건너뜀"),
+    ("eol", "줄바꿈"),
+    ("keep", "유지"),
+    ("dir", "폴더 변환"),
+    ("select_dir", "루트 폴더 선택"),
+    ("select_outdir", "출력 폴더 선택"),
+    ("pattern", "파일명 필터"),
+    ("in_place", "제자리 변환"),
+    ("results", "변환 결과"),
+    ("backup", "먼저 백업 (.bak)"),
+    ("preview", "바이트 미리보기"),
+    ("in_escaped", "입력은 이스케이프된 바이트"),
+    ("in_base64", "입력은 Base64"),
+    ("as_html", "HTML 로 처리"),
+    ("looks_hex", "16진수 바이트 같습니다 — 먼저 디코딩할까요?"),
+    ("looks_pct", "퍼센트 인코딩 같습니다 — 먼저 디코딩할까요?"),
+    ("looks_b64", "Base64 같습니다 — 먼저 디코딩할까요?"),
+    ("out_repr", "출력 형식"),
+    ("repr_text", "텍스트"),
+    ("theme", "테마"),
+    ("theme_default", "기본"),
+    ("theme_hc", "고대비"),
+    ("theme_large", "큰 글자"),
+    ("theme_system", "시스템 따름"),
+    ("theme_light", "밝게"),
+    ("theme_dark", "어둡게"),
+    ("font_size", "글자 크기"),
+    ("recent", "최근 작업"),
+    ("repeat", "다시 실행"),
+    ("open_folder", "폴더 열기"),
+    ("open_file", "파일 열기"),
+    ("auto_open", "완료 후 폴더 자동 열기"),
+    ("incremental", "증분 (변경 없으면 건너뜀)"),
+    ("unchanged", "변경 없음, 건너뜀"),
+    ("regressed", "이전 인코딩으로 되돌아감"),
+    ("from_url", "URL에서"),
+    ("fetch", "가져오기"),
+    ("fetched", "다운로드 완료:"),
+    ("fallback", "대체 인코딩 체인"),
+    (
+        "fallback_hint",
+        "순서대로 시도해 대체 문자가 없는 첫 인코딩을 사용. 비워 두면 위의 원본 인코딩 사용",
+    ),
+    ("unmappable", "매핑 불가"),
+    ("eta", "남은 시간"),
+    ("apply_all", "전체에 적용"),
+    ("compare", "변환 전후 비교 (처음 200줄)"),
+    ("commit", "쓰기 확정"),
+    ("cancel", "취소"),
+    (
+        "lost_chars",
+        "개 문자를 대상 인코딩으로 표현할 수 없어 대체됩니다",
+    ),
+    ("lost_samples", "예시"),
+    ("tab_before", "변환 전"),
+    ("tab_after", "변환 후"),
+    ("zip_pwd", "압축 파일 암호 (선택)"),
+    ("sidecar", ".meta.json 사이드카 기록"),
+    ("revert_meta", "사이드카로 되돌리기"),
+    ("revert_inexact", "원본과 완전히 일치하지 않음"),
+    ("verify", "쓰기 후 검증"),
+    ("verify_fail", "쓰기 후 검증 불일치"),
+    ("binary", "바이너리 파일"),
+    ("force", "강제 변환"),
+    ("skipped_binary", "바이너리로 보여 건너뜀"),
+    (
+        "binary_warn",
+        "바이너리로 보입니다. 변환하면 데이터가 손상될 수 있음:",
+    ),
+    ("dismiss_all", "모두 닫기"),
+    ("pair_defaults", "인코딩 쌍 기본값을 적용했습니다"),
+    ("sub_suffix", "자막 언어 접미사 (예 zh, 선택)"),
+    ("view_only", "보기만 (쓰지 않음)"),
+    ("viewer", "읽기 전용 보기"),
+    ("sandbox", "신뢰 폴더에만 쓰기 허용"),
+    ("trusted_dirs", "신뢰 폴더"),
+    ("add_trusted", "신뢰 폴더 추가"),
+    ("sandbox_blocked", "대상이 신뢰 폴더 밖입니다:"),
+    ("trust_dir", "이 폴더 신뢰"),
+    ("allow_once", "이번 실행만 허용"),
+    ("preset", "프리셋"),
+    ("preset_pick", "프리셋 적용…"),
+    ("preset_name", "프리셋 이름"),
+    ("save_preset", "프리셋 저장"),
+    ("suffix_out", "출력명에 인코딩 접미사"),
+    ("name_tpl", "이름 템플릿 ({stem} {ext} {relpath})"),
+    (
+        "same_path",
+        "출력이 입력 파일 자체라 제자리에서 덮어씁니다:",
+    ),
+    ("pair_subs", "동영상과 같은 이름의 자막만"),
+    ("mixed_eol", "줄바꿈이 섞여 있습니다"),
+    ("fix_eol", "다수로 통일"),
+    ("err_io", "입출력 오류:"),
+    ("err_perm", "권한이 없습니다:"),
+    ("err_decode", "디코딩 오류:"),
+    ("err_unmap", "개 문자가 대상 인코딩에 없습니다"),
+    ("bytes", "바이트"),
+    ("done_n", "완료:"),
+    ("analyzed_n", "분석됨:"),
+    ("files_n", "개 파일"),
+    ("log", "로그"),
+    ("log_all", "전체"),
+    ("log_warn", "경고 이상"),
+    ("log_err", "오류만"),
+    ("copy_log", "로그 복사"),
+    ("menu_file", "파일"),
+    ("open", "열기… (Ctrl+O)"),
+    ("save_out", "출력 저장…"),
+    ("quit", "종료"),
+    ("menu_edit", "편집"),
+    ("menu_mode", "모드"),
+    ("swap", "인코딩 서로 바꾸기 (Ctrl+L)"),
+    ("char_map", "문자표"),
+    ("char_search", "이름 또는 코드 포인트 검색 (U+XXXX)"),
+    ("rep_entities", "HTML 엔티티 디코딩"),
+    ("rep_eol", "줄바꿈 통일"),
+    ("rep_replaced", "인코딩 불가 문자 대체"),
+    ("names", "파일명 변환"),
+    ("preview_names", "이름 바꾸기 미리보기"),
+    ("apply_names", "이름 바꾸기 실행"),
+    ("undo_names", "마지막 이름 바꾸기 취소"),
+    ("no_renames", "변환할 파일명이 없습니다"),
+    ("repair", "깨진 글자 복구"),
+    ("playground", "디코딩 비교"),
+    ("play_input", "바이트 입력 (16진 / \\x / %)"),
+    ("garbled", "깨진 텍스트 붙여넣기"),
+    ("analyze", "분석"),
+    ("candidates", "복구 후보"),
+    ("use_candidate", "사용"),
+    ("no_candidates", "더 읽기 쉬운 디코딩을 찾지 못했습니다"),
+    ("paste_clip", "클립보드 붙여넣기"),
+    ("copy_out", "결과 복사"),
+    ("convert_clip", "클립보드 변환"),
+    ("clip_done", "클립보드를 변환했습니다"),
+    ("clip_empty", "클립보드에 텍스트가 없습니다"),
+    ("paste_files", "파일 붙여넣기"),
+    ("add_dir", "폴더 추가"),
+    ("add_files", "파일 추가"),
+    ("clear_roots", "목록 비우기"),
+    ("analyze_only", "분석만"),
+    ("analysis", "분석 결과"),
+    ("export_csv", "CSV 내보내기"),
+    ("col_file", "파일"),
+    ("col_enc", "인코딩"),
+    ("col_bom", "BOM"),
+    ("col_eol", "줄바꿈"),
+    ("col_lossy", "손실"),
+    ("col_snippet", "첫 줄"),
+    ("yes", "예"),
+    ("no", "아니요"),
+    ("clip_no_files", "클립보드에 파일이 없습니다"),
+];
+
+static RU: Strings = &[
+    ("text", "Текст"),
+    ("file", "Файл"),
+    ("from", "Из"),
+    ("to", "В"),
+    ("start", "Начать"),
+    ("input", "Входной текст"),
+    ("output", "Результат"),
+    ("select_input", "Выбрать входной файл"),
+    ("select_output", "Выбрать выходной файл"),
+    ("working", "Преобразование..."),
+    ("idle", "Ожидание"),
+    ("strip_bom", "Убрать BOM источника"),
+    ("write_bom", "Записать BOM в результат"),
+    ("conflict", "При совпадении имён"),
+    ("overwrite", "Перезаписать"),
+    ("skip", "Пропустить"),
+    ("rename", "Переименовать"),
+    ("ask", "Спрашивать"),
+    ("exists", "Выходной файл уже существует:"),
+    ("skipped", "Пропущено"),
+    ("eol", "Переводы строк"),
+    ("keep", "Оставить"),
+    ("dir", "Папка"),
+    ("select_dir", "Выбрать корневую папку"),
+    ("select_outdir", "Выбрать выходную папку"),
+    ("pattern", "Фильтр имён"),
+    ("in_place", "На месте"),
+    ("results", "Результаты"),
+    ("backup", "Сначала резервная копия (.bak)"),
+    ("preview", "Просмотр байтов"),
+    ("in_escaped", "Ввод — экранированные байты"),
+    ("in_base64", "Ввод — Base64"),
+    ("as_html", "Обрабатывать как HTML"),
+    (
+        "looks_hex",
+        "Похоже на шестнадцатеричные байты — сначала декодировать?",
+    ),
+    (
+        "looks_pct",
+        "Похоже на percent-кодирование — сначала декодировать?",
+    ),
+    ("looks_b64", "Похоже на Base64 — сначала декодировать?"),
+    ("out_repr", "Формат вывода"),
+    ("repr_text", "Текст"),
+    ("theme", "Тема"),
+    ("theme_default", "Стандартная"),
+    ("theme_hc", "Высокий контраст"),
+    ("theme_large", "Крупный текст"),
+    ("theme_system", "Как в системе"),
+    ("theme_light", "Светлая"),
+    ("theme_dark", "Тёмная"),
+    ("font_size", "Размер шрифта"),
+    ("recent", "Недавние"),
+    ("repeat", "Повторить"),
+    ("open_folder", "Открыть папку"),
+    ("open_file", "Открыть файл"),
+    ("auto_open", "Открывать папку после завершения"),
+    ("incremental", "Инкрементально (пропускать неизменённые)"),
+    ("unchanged", "без изменений, пропущено"),
+    ("regressed", "вернулся к старой кодировке"),
+    ("from_url", "Из URL"),
+    ("fetch", "Загрузить"),
+    ("fetched", "Загружено:"),
+    ("fallback", "Цепочка запасных кодировок"),
+    (
+        "fallback_hint",
+        "Пробуются по порядку; берётся первая без символов замены. Пусто — кодировка выше",
+    ),
+    ("unmappable", "не отображается"),
+    ("eta", "осталось"),
+    ("apply_all", "применить ко всем"),
+    ("compare", "До / после (первые 200 строк)"),
+    ("commit", "Записать"),
+    ("cancel", "Отмена"),
+    (
+        "lost_chars",
+        "символ(ов) нельзя представить в целевой кодировке, они будут заменены",
+    ),
+    ("lost_samples", "Примеры"),
+    ("tab_before", "До"),
+    ("tab_after", "После"),
+    ("zip_pwd", "Пароль архива (необязательно)"),
+    ("sidecar", "Записывать .meta.json рядом"),
+    ("revert_meta", "Откатить по метаданным"),
+    ("revert_inexact", "не совпадает с оригиналом"),
+    ("verify", "Проверять после записи"),
+    ("verify_fail", "несовпадение при проверке после записи"),
+    ("binary", "Двоичные файлы"),
+    ("force", "Принудительно"),
+    ("skipped_binary", "похоже на двоичный файл, пропущен"),
+    (
+        "binary_warn",
+        "Похоже на двоичный файл — преобразование может испортить данные:",
+    ),
+    ("dismiss_all", "Закрыть все"),
+    (
+        "pair_defaults",
+        "Применены настройки для этой пары кодировок",
+    ),
+    (
+        "sub_suffix",
+        "Языковой суффикс субтитров (напр. zh, необязательно)",
+    ),
+    ("view_only", "Только просмотр (без записи)"),
+    ("viewer", "Просмотр без записи"),
+    ("sandbox", "Писать только в доверенные папки"),
+    ("trusted_dirs", "Доверенные папки"),
+    ("add_trusted", "Добавить доверенную папку"),
+    ("sandbox_blocked", "Цель вне доверенных папок:"),
+    ("trust_dir", "Доверять этой папке"),
+    ("allow_once", "Разрешить на этот запуск"),
+    ("preset", "Пресет"),
+    ("preset_pick", "Применить пресет…"),
+    ("preset_name", "Имя пресета"),
+    ("save_preset", "Сохранить пресет"),
+    ("suffix_out", "Добавлять кодировку к имени"),
+    ("name_tpl", "Шаблон имени ({stem} {ext} {relpath})"),
+    (
+        "same_path",
+        "Вывод совпадает с входным файлом и будет перезаписан на месте:",
+    ),
+    ("pair_subs", "Только субтитры с совпадающим видео"),
+    ("mixed_eol", "Обнаружены смешанные переводы строк"),
+    ("fix_eol", "Привести к большинству"),
+    ("err_io", "Ошибка ввода-вывода:"),
+    ("err_perm", "Нет доступа:"),
+    ("err_decode", "Ошибки декодирования в"),
+    (
+        "err_unmap",
+        "символ(ов) не отображаются в целевую кодировку",
+    ),
+    ("bytes", "байт"),
+    ("done_n", "Готово:"),
+    ("analyzed_n", "Проанализировано:"),
+    ("files_n", "файлов"),
+    ("log", "Журнал"),
+    ("log_all", "Все"),
+    ("log_warn", "Предупреждения+"),
+    ("log_err", "Только ошибки"),
+    ("copy_log", "Копировать журнал"),
+    ("menu_file", "Файл"),
+    ("open", "Открыть… (Ctrl+O)"),
+    ("save_out", "Сохранить вывод…"),
+    ("quit", "Выход"),
+    ("menu_edit", "Правка"),
+    ("menu_mode", "Режим"),
+    ("swap", "Поменять кодировки местами (Ctrl+L)"),
+    ("char_map", "Таблица символов"),
+    ("char_search", "Поиск по имени или коду (U+XXXX)"),
+    ("rep_entities", "HTML-сущности декодированы"),
+    ("rep_eol", "переводы строк приведены"),
+    ("rep_replaced", "незакодируемые заменены"),
+    ("names", "Имена файлов"),
+    ("preview_names", "Предпросмотр переименования"),
+    ("apply_names", "Переименовать"),
+    ("undo_names", "Отменить переименование"),
+    ("no_renames", "Имена файлов не требуют преобразования"),
+    ("repair", "Исправить кракозябры"),
+    ("playground", "Песочница"),
+    ("play_input", "Ввод байтов (hex / \\x / %)"),
+    ("garbled", "Вставьте искажённый текст"),
+    ("analyze", "Анализ"),
+    ("candidates", "Варианты"),
+    ("use_candidate", "Применить"),
+    ("no_candidates", "Более читаемого декодирования не найдено"),
+    ("paste_clip", "Вставить из буфера"),
+    ("copy_out", "Копировать результат"),
+    ("convert_clip", "Преобразовать буфер обмена"),
+    ("clip_done", "Буфер обмена преобразован"),
+    ("clip_empty", "В буфере обмена нет текста"),
+    ("paste_files", "Вставить файлы"),
+    ("add_dir", "Добавить папку"),
+    ("add_files", "Добавить файлы"),
+    ("clear_roots", "Очистить список"),
+    ("analyze_only", "Только анализ"),
+    ("analysis", "Анализ"),
+    ("export_csv", "Экспорт CSV"),
+    ("col_file", "Файл"),
+    ("col_enc", "Кодировка"),
+    ("col_bom", "BOM"),
+    ("col_eol", "EOL"),
+    ("col_lossy", "Потери"),
+    ("col_snippet", "Первая строка"),
+    ("yes", "Да"),
+    ("no", "Нет"),
+    ("clip_no_files", "В буфере обмена нет файлов"),
+];
+
+fn lang_table(lang: Language) -> Strings {
     match lang {
-        Language::Zh => match key {
-            "text" => "文本转码",
-            "file" => "文件转码",
-            "from" => "来源编码",
-            "to" => "目标编码",
-            "start" => "开始转码",
-            "input" => "输入文本",
-            "output" => "输出结果",
-            "select_input" => "选择输入文件",
-            "select_output" => "选择输出文件",
-            "working" => "正在转码...",
-            "idle" => "暂无状态",
-            _ => key,
-        },
-        Language::En => match key {
-            "text" => "Text",
-            "file" => "File",
-            "from" => "From",
-            "to" => "To",
-            "start" => "Start",
-            "input" => "Input Text",
-            "output" => "Output",
-            "select_input" => "Select Input File",
-            "select_output" => "Select Output File",
-            "working" => "Working...",
-            "idle" => "Idle",
-            _ => key,
-        },
+        Language::Zh => ZH,
+        Language::ZhTw => ZH_TW,
+        Language::En => EN,
+        Language::Ja => JA,
+        Language::Ko => KO,
+        Language::Ru => RU,
     }
 }
 
+fn lookup(table: Strings, key: &str) -> Option<&'static str> {
+    table.iter().find(|(k, _)| *k == key).map(|(_, v)| *v)
+}
+
+fn t(key: &str, lang: Language) -> &str {
+    lookup(lang_table(lang), key)
+        .or_else(|| lookup(EN, key))
+        .unwrap_or(key)
+}
+
 /* ======================= 模式 ======================= */
 #[derive(Clone, Copy, PartialEq)]
 enum Mode {
     Text,
     File,
+    Dir,
+    Names,
+    Repair,
+    Playground,
 }
 
-/* ======================= 转码逻辑 ======================= */
-fn transcode_text(input: &str, from: usize, to: usize) -> String {
-    let (from_enc, _) = ENCODINGS[from];
-    let (to_enc, _) = ENCODINGS[to];
+/* ======================= 主题 ======================= */
+/*
+    为低视力用户准备的无障碍预设,
+    比单纯的缩放更进一步
+*/
+#[derive(Clone, Copy, PartialEq)]
+enum ThemePreset {
+    Default,
+    HighContrast,
+    LargeText,
+}
+
+/* 明暗跟随系统还是手动指定, 高分屏上还能单独调字号 */
+#[derive(Clone, Copy, PartialEq)]
+enum ColorMode {
+    System,
+    Light,
+    Dark,
+}
 
-    let (decoded, _, _) = from_enc.decode(input.as_bytes());
-    let (encoded, _, _) = to_enc.encode(&decoded);
+impl ColorMode {
+    fn tag(self) -> &'static str {
+        match self {
+            ColorMode::System => "system",
+            ColorMode::Light => "light",
+            ColorMode::Dark => "dark",
+        }
+    }
 
-    String::from_utf8_lossy(&encoded).to_string()
+    fn from_tag(tag: &str) -> Option<ColorMode> {
+        [ColorMode::System, ColorMode::Light, ColorMode::Dark]
+            .into_iter()
+            .find(|m| m.tag() == tag)
+    }
 }
 
-fn transcode_file(input: PathBuf, output: PathBuf, from: usize, to: usize) -> String {
-    let data = match std::fs::read(&input) {
-        Ok(v) => v,
-        Err(e) => return e.to_string(),
+fn apply_theme(ctx: &egui::Context, preset: ThemePreset, colors: ColorMode, font_scale: f32) {
+    /* 先回到默认状态,预设才能重复切换 */
+    let mut style = egui::Style::default();
+    let mut visuals = match colors {
+        ColorMode::System => match ctx.system_theme() {
+            Some(egui::Theme::Light) => egui::Visuals::light(),
+            _ => egui::Visuals::dark(),
+        },
+        ColorMode::Light => egui::Visuals::light(),
+        ColorMode::Dark => egui::Visuals::dark(),
     };
 
-    let (from_enc, _) = ENCODINGS[from];
-    let (to_enc, _) = ENCODINGS[to];
+    match preset {
+        ThemePreset::Default => {}
+        ThemePreset::HighContrast => {
+            visuals.override_text_color = Some(egui::Color32::WHITE);
+            visuals.panel_fill = egui::Color32::BLACK;
+            visuals.extreme_bg_color = egui::Color32::BLACK;
+            visuals.widgets.noninteractive.bg_fill = egui::Color32::BLACK;
+            visuals.selection.bg_fill = egui::Color32::YELLOW;
+            visuals.selection.stroke = egui::Stroke::new(1.0, egui::Color32::BLACK);
+        }
+        ThemePreset::LargeText => {
+            for font_id in style.text_styles.values_mut() {
+                font_id.size *= 1.6;
+            }
+        }
+    }
+    for font_id in style.text_styles.values_mut() {
+        font_id.size *= font_scale;
+    }
 
-    let (decoded, _, _) = from_enc.decode(&data);
-    let (encoded, _, _) = to_enc.encode(&decoded);
+    style.visuals = visuals;
+    ctx.set_style(style);
+}
 
-    match std::fs::write(&output, encoded) {
-        Ok(_) => format!("Done: {}", output.display()),
-        Err(e) => e.to_string(),
-    }
+/* ======================= 换行符 ======================= */
+/*
+    解码之后、重新编码之前统一换行符,
+    Keep 表示保持原样
+*/
+#[derive(Clone, Copy, PartialEq)]
+enum LineEnding {
+    Keep,
+    Lf,
+    Crlf,
+    Cr,
 }
 
-/* ======================= App ======================= */
-struct CodeTransApp {
-    lang: Language,
-    mode: Mode,
+fn normalize_eol(text: &str, eol: LineEnding) -> String {
+    let target = match eol {
+        LineEnding::Keep => return text.to_string(),
+        LineEnding::Lf => "\n",
+        LineEnding::Crlf => "\r\n",
+        LineEnding::Cr => "\r",
+    };
 
-    from_idx: usize,
-    to_idx: usize,
+    let unified = text.replace("\r\n", "\n").replace('\r', "\n");
+    if target == "\n" {
+        unified
+    } else {
+        unified.replace('\n', target)
+    }
+}
 
-    input_text: String,
-    output_text: String,
+/* ======================= 冲突处理 ======================= */
+/*
+    输出文件已存在时的策略,Ask 在界面上弹出提示,
+    不阻塞其它转码任务
+*/
+#[derive(Clone, Copy, PartialEq)]
+enum ConflictPolicy {
+    Ask,
+    Overwrite,
+    Skip,
+    Rename,
+}
 
-    input_file: Option<PathBuf>,
-    output_file: Option<PathBuf>,
+/* 生成不冲突的新文件名: out.txt -> out (1).txt */
+fn renamed_path(path: &Path) -> PathBuf {
+    let stem = path
+        .file_stem()
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let ext = path.extension().map(|s| s.to_string_lossy().into_owned());
 
-    status: String,
+    for n in 1.. {
+        let name = match &ext {
+            Some(e) => format!("{} ({}).{}", stem, n, e),
+            None => format!("{} ({})", stem, n),
+        };
+        let candidate = path.with_file_name(name);
+        if !candidate.exists() {
+            return candidate;
+        }
+    }
+    unreachable!()
+}
 
-    rx: Option<mpsc::Receiver<String>>,
+/* 输出路径是否就是输入文件本身, 符号链接也算 */
+fn same_file(a: &Path, b: &Path) -> bool {
+    if a == b {
+        return true;
+    }
+    match (a.canonicalize(), b.canonicalize()) {
+        (Ok(x), Ok(y)) => x == y,
+        _ => false,
+    }
 }
 
-impl Default for CodeTransApp {
-    fn default() -> Self {
-        Self {
-            lang: Language::Zh,
-            mode: Mode::Text,
-            from_idx: 0,
-            to_idx: 3, // UTF-8 -> GBK
-            input_text: String::new(),
-            output_text: String::new(),
-            input_file: None,
-            output_file: None,
-            status: t("idle", Language::Zh).into(),
-            rx: None,
-        }
+/* 按目标编码生成建议输出路径: a.txt + GBK -> a_gbk.txt */
+fn suggested_output(input: &Path, to_idx: usize) -> PathBuf {
+    let tag: String = ENCODINGS[to_idx]
+        .1
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .collect::<String>()
+        .to_lowercase();
+    let stem = input
+        .file_stem()
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let name = match input.extension() {
+        Some(e) => format!("{}_{}.{}", stem, tag, e.to_string_lossy()),
+        None => format!("{}_{}", stem, tag),
+    };
+    input.with_file_name(name)
+}
+
+/* 批量模式的命名模板: {stem} {ext} {relpath} 三个占位符。
+模板里没写 {relpath} 时保留原来的子目录结构 */
+fn apply_name_template(template: &str, rel: &Path) -> PathBuf {
+    let stem = rel
+        .file_stem()
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let ext = rel
+        .extension()
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let name = template
+        .replace("{stem}", &stem)
+        .replace("{ext}", &ext)
+        .replace("{relpath}", &rel.display().to_string());
+    /* 没有扩展名时把模板留下的尾点去掉 */
+    let name = name.trim_end_matches('.').to_string();
+
+    if template.contains("{relpath}") {
+        PathBuf::from(name)
+    } else {
+        rel.parent().unwrap_or(Path::new("")).join(name)
     }
 }
 
-impl App for CodeTransApp {
-    fn update(&mut self, ctx: &egui::Context, _: &mut eframe::Frame) {
-        egui::CentralPanel::default().show(ctx, |ui| {
-            ui.horizontal(|ui| {
-                if ui.button("中文").clicked() {
-                    self.lang = Language::Zh;
-                }
-                if ui.button("EN").clicked() {
-                    self.lang = Language::En;
-                }
-            });
+/* ======================= 信任目录 ======================= */
+/*
+    可选的安全开关: 开启后写盘操作只允许落在用户批准的
+    信任目录里,避免误点原地批量把系统目录或仓库改掉。
+    被拦截的任务存起来,界面上提示后可以放行
+*/
+enum SandboxAction {
+    Single(PathBuf, PathBuf),
+    Batch,
+    /* 文件名转码的批量改名 / 撤销, 根目录在 input_dir 里 */
+    Rename,
+    UndoRename,
+}
 
-            ui.separator();
+/* ======================= 预设 ======================= */
+/*
+    具名转换预设: 源/目标编码、BOM 策略、换行策略和
+    输出命名规则打包保存, 下拉一键套用, Ctrl+1..9 也可以。
+    随其它设置一起存进 eframe storage, 一行一条
+*/
+#[derive(Clone)]
+struct Preset {
+    name: String,
+    from: usize,
+    to: usize,
+    strip_bom: bool,
+    write_bom: bool,
+    eol: LineEnding,
+    suffix: bool,
+}
 
-            ui.horizontal(|ui| {
-                ui.selectable_value(&mut self.mode, Mode::Text, t("text", self.lang));
-                ui.selectable_value(&mut self.mode, Mode::File, t("file", self.lang));
-            });
+fn eol_tag(eol: LineEnding) -> &'static str {
+    match eol {
+        LineEnding::Keep => "keep",
+        LineEnding::Lf => "lf",
+        LineEnding::Crlf => "crlf",
+        LineEnding::Cr => "cr",
+    }
+}
 
-            ui.separator();
+fn eol_from_tag(tag: &str) -> LineEnding {
+    match tag {
+        "lf" => LineEnding::Lf,
+        "crlf" => LineEnding::Crlf,
+        "cr" => LineEnding::Cr,
+        _ => LineEnding::Keep,
+    }
+}
 
-            ui.horizontal(|ui| {
-                ui.label(t("from", self.lang));
-                encoding_combo(ui, "from", &mut self.from_idx);
-                ui.label(t("to", self.lang));
-                encoding_combo(ui, "to", &mut self.to_idx);
-            });
+fn presets_to_string(list: &[Preset]) -> String {
+    list.iter()
+        .map(|p| {
+            format!(
+                "{}\t{}\t{}\t{}\t{}\t{}\t{}",
+                p.name,
+                p.from,
+                p.to,
+                p.strip_bom as u8,
+                p.write_bom as u8,
+                eol_tag(p.eol),
+                p.suffix as u8,
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
 
-            ui.separator();
+/* 对不上的行直接丢掉, 坏一条不影响其它预设 */
+fn presets_from_string(s: &str) -> Vec<Preset> {
+    s.lines()
+        .filter_map(|line| {
+            let f: Vec<&str> = line.split('\t').collect();
+            if f.len() != 7 || f[0].is_empty() {
+                return None;
+            }
+            let from: usize = f[1].parse().ok()?;
+            let to: usize = f[2].parse().ok()?;
+            if from >= ENCODINGS.len() || to >= ENCODINGS.len() {
+                return None;
+            }
+            Some(Preset {
+                name: f[0].to_string(),
+                from,
+                to,
+                strip_bom: f[3] == "1",
+                write_bom: f[4] == "1",
+                eol: eol_from_tag(f[5]),
+                suffix: f[6] == "1",
+            })
+        })
+        .collect()
+}
 
-            match self.mode {
-                Mode::Text => self.ui_text(ui),
-                Mode::File => self.ui_file(ui),
+/* ======================= BOM ======================= */
+/*
+    UTF 系编码的 BOM 处理由 encodeconverter-core 提供:
+    strip_bom  解码前去掉已有 BOM
+    write_bom  编码后在目标是 UTF-8/UTF-16 时写入 BOM
+*/
+
+/* ======================= UTF-16/32 手工编解码 ======================= */
+/*
+    encoding_rs 的 encoder 不会产出 UTF-16, UTF-32 则完全没有,
+    这里按 ENCODINGS 下标分发: UTF-16/32 目标手工编码,
+    UTF-32 源手工解码, 其余仍交给 encoding_rs。
+    代理对由 encode_utf16 处理, chars() 不会产出孤立代理
+*/
+fn encode_idx(idx: usize, text: &str) -> (Vec<u8>, bool) {
+    let (enc, label) = ENCODINGS[idx];
+    let mut out = Vec::with_capacity(text.len() * 2);
+    match label {
+        "UTF-16LE" => {
+            for u in text.encode_utf16() {
+                out.extend_from_slice(&u.to_le_bytes());
+            }
+        }
+        "UTF-16BE" => {
+            for u in text.encode_utf16() {
+                out.extend_from_slice(&u.to_be_bytes());
+            }
+        }
+        "UTF-32LE" => {
+            for c in text.chars() {
+                out.extend_from_slice(&(c as u32).to_le_bytes());
+            }
+        }
+        "UTF-32BE" => {
+            for c in text.chars() {
+                out.extend_from_slice(&(c as u32).to_be_bytes());
             }
+        }
+        _ => {
+            let (encoded, _, had_errors) = enc.encode(text);
+            return (encoded.into_owned(), had_errors);
+        }
+    }
+    (out, false)
+}
 
-            if let Some(rx) = &self.rx {
-                if let Ok(msg) = rx.try_recv() {
-                    match self.mode {
-                        Mode::Text => self.output_text = msg,
-                        Mode::File => self.status = msg,
-                    }
-                }
+/* 4 字节一组, 非法标量和残缺尾部都换成替换符 */
+fn decode_utf32(data: &[u8], le: bool) -> String {
+    let mut out = String::with_capacity(data.len() / 4);
+    for chunk in data.chunks(4) {
+        let c = match <[u8; 4]>::try_from(chunk) {
+            Ok(b) => {
+                let v = if le {
+                    u32::from_le_bytes(b)
+                } else {
+                    u32::from_be_bytes(b)
+                };
+                char::from_u32(v).unwrap_or('\u{FFFD}')
             }
-        });
+            Err(_) => '\u{FFFD}',
+        };
+        out.push(c);
     }
+    out
 }
 
-/* ======================= UI ======================= */
-impl CodeTransApp {
-    fn ui_text(&mut self, ui: &mut egui::Ui) {
-        ui.label(t("input", self.lang));
-        ui.text_edit_multiline(&mut self.input_text);
+fn decode_idx(idx: usize, data: &[u8]) -> String {
+    let (enc, label) = ENCODINGS[idx];
+    match label {
+        "UTF-32LE" => decode_utf32(data, true),
+        "UTF-32BE" => decode_utf32(data, false),
+        _ => enc.decode_without_bom_handling(data).0.into_owned(),
+    }
+}
 
-        if ui.button(t("start", self.lang)).clicked() {
-            let (tx, rx) = mpsc::channel();
-            let input = self.input_text.clone();
-            let from = self.from_idx;
-            let to = self.to_idx;
-            self.rx = Some(rx);
+fn is_manual_target(idx: usize) -> bool {
+    matches!(
+        ENCODINGS[idx].1,
+        "UTF-16LE" | "UTF-16BE" | "UTF-32LE" | "UTF-32BE"
+    )
+}
 
-            thread::spawn(move || {
-                tx.send(transcode_text(&input, from, to)).ok();
-            });
-        }
+fn bom_of_idx(idx: usize) -> Option<&'static [u8]> {
+    match ENCODINGS[idx].1 {
+        "UTF-32LE" => Some(&[0xFF, 0xFE, 0x00, 0x00]),
+        "UTF-32BE" => Some(&[0x00, 0x00, 0xFE, 0xFF]),
+        _ => bom_of(ENCODINGS[idx].0),
+    }
+}
 
-        ui.separator();
-        ui.label(t("output", self.lang));
-        ui.text_edit_multiline(&mut self.output_text);
+fn strip_bom_idx(data: &[u8], idx: usize) -> &[u8] {
+    match bom_of_idx(idx) {
+        Some(bom) if data.starts_with(bom) => &data[bom.len()..],
+        _ => data,
     }
+}
 
-    fn ui_file(&mut self, ui: &mut egui::Ui) {
-        if ui.button(t("select_input", self.lang)).clicked() {
-            self.input_file = rfd::FileDialog::new().pick_file();
-        }
-        if ui.button(t("select_output", self.lang)).clicked() {
-            self.output_file = rfd::FileDialog::new().save_file();
+/* ======================= 目录遍历 ======================= */
+/*
+    文件名过滤用分号分隔的简单通配符,
+    只支持 * 和 ?,例如 "*.txt;*.csv;*.srt"
+*/
+fn glob_match(pattern: &str, name: &str) -> bool {
+    let p: Vec<char> = pattern.chars().collect();
+    let n: Vec<char> = name.chars().collect();
+
+    /* 经典回溯匹配 */
+    let (mut pi, mut ni) = (0, 0);
+    let (mut star, mut mark) = (None, 0);
+    while ni < n.len() {
+        if pi < p.len() && (p[pi] == '?' || p[pi].eq_ignore_ascii_case(&n[ni])) {
+            pi += 1;
+            ni += 1;
+        } else if pi < p.len() && p[pi] == '*' {
+            star = Some(pi);
+            mark = ni;
+            pi += 1;
+        } else if let Some(s) = star {
+            pi = s + 1;
+            mark += 1;
+            ni = mark;
+        } else {
+            return false;
         }
+    }
+    while pi < p.len() && p[pi] == '*' {
+        pi += 1;
+    }
+    pi == p.len()
+}
+
+fn matches_patterns(patterns: &str, name: &str) -> bool {
+    patterns
+        .split(';')
+        .map(str::trim)
+        .filter(|p| !p.is_empty())
+        .any(|p| glob_match(p, name))
+}
 
-        if ui.button(t("start", self.lang)).clicked() {
-            if let (Some(i), Some(o)) = (self.input_file.clone(), self.output_file.clone()) {
-                self.status = t("working", self.lang).into();
-                let (tx, rx) = mpsc::channel();
-                let from = self.from_idx;
-                let to = self.to_idx;
-                self.rx = Some(rx);
+/* 递归收集匹配的文件,返回相对路径 */
+fn collect_files(root: &Path, patterns: &str) -> Vec<PathBuf> {
+    let mut out = Vec::new();
+    let mut stack = vec![root.to_path_buf()];
 
-                thread::spawn(move || {
-                    tx.send(transcode_file(i, o, from, to)).ok();
-                });
+    while let Some(dir) = stack.pop() {
+        let Ok(entries) = std::fs::read_dir(&dir) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.is_dir() {
+                stack.push(path);
+            } else if let Some(name) = path.file_name().and_then(|s| s.to_str())
+                && matches_patterns(patterns, name)
+                && let Ok(rel) = path.strip_prefix(root)
+            {
+                out.push(rel.to_path_buf());
             }
         }
+    }
+    out.sort();
+    out
+}
 
-        ui.separator();
-        ui.label(&self.status);
+/* ======================= .reg 文件 ======================= */
+/*
+    regedit 的导出文件有两种形式:
+    Unicode: UTF-16LE + BOM,头部 "Windows Registry Editor Version 5.00"
+    ANSI:    代码页编码,头部 "REGEDIT4"
+    转换时要同时改写头部和 BOM,否则 regedit 拒绝导入
+*/
+const REG_HEADER_UNICODE: &str = "Windows Registry Editor Version 5.00";
+const REG_HEADER_ANSI: &str = "REGEDIT4";
+
+fn is_reg_file(path: &Path) -> bool {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| e.eq_ignore_ascii_case("reg"))
+}
+
+/* encoding_rs 的编码器不支持 UTF-16,这里手动生成 */
+fn encode_utf16le_bom(text: &str) -> Vec<u8> {
+    let mut out = vec![0xFF, 0xFE];
+    for u in text.encode_utf16() {
+        out.extend_from_slice(&u.to_le_bytes());
     }
+    out
 }
 
-/* ======================= 编码下拉 ======================= */
-fn encoding_combo(ui: &mut egui::Ui, id: &str, value: &mut usize) {
-    egui::ComboBox::from_id_salt(id)
-        .selected_text(ENCODINGS[*value].1)
-        .show_ui(ui, |ui| {
-            for (i, (_, label)) in ENCODINGS.iter().enumerate() {
-                ui.selectable_value(value, i, *label);
+fn transcode_reg(data: &[u8], ansi_from: &'static Encoding, to_enc: &'static Encoding) -> Vec<u8> {
+    /* 按 BOM 判断来源形式,没有 BOM 按 ANSI 处理 */
+    let decoded = if data.starts_with(&[0xFF, 0xFE]) {
+        let (s, _) = UTF_16LE.decode_without_bom_handling(&data[2..]);
+        s.into_owned()
+    } else {
+        let (s, _) = ansi_from.decode_without_bom_handling(data);
+        s.into_owned()
+    };
+
+    /* 头部改写成与目标形式一致 */
+    let to_unicode = to_enc == UTF_16LE || to_enc == UTF_16BE;
+    let header = if to_unicode {
+        REG_HEADER_UNICODE
+    } else {
+        REG_HEADER_ANSI
+    };
+
+    let rewritten = match decoded.split_once('\n') {
+        Some((first, rest))
+            if first.trim() == REG_HEADER_UNICODE || first.trim() == REG_HEADER_ANSI =>
+        {
+            format!("{}\r\n{}", header, rest)
+        }
+        _ => decoded,
+    };
+
+    if to_unicode {
+        encode_utf16le_bom(&rewritten)
+    } else {
+        let (encoded, _, _) = to_enc.encode(&rewritten);
+        encoded.into_owned()
+    }
+}
+
+/* ======================= 十六进制预览 ======================= */
+/*
+    只读取文件开头一小段,随 from 编码实时解码,
+    方便在整文件转换前核对编码猜测
+*/
+const PREVIEW_LEN: usize = 4096;
+
+fn hex_dump(data: &[u8]) -> String {
+    let mut out = String::new();
+    for (i, chunk) in data.chunks(16).enumerate() {
+        out.push_str(&format!("{:08X}  ", i * 16));
+        for b in chunk {
+            out.push_str(&format!("{:02X} ", b));
+        }
+        out.push('\n');
+    }
+    out
+}
+
+fn read_preview(path: &Path) -> Option<Vec<u8>> {
+    let mut data = std::fs::read(path).ok()?;
+    data.truncate(PREVIEW_LEN);
+    Some(data)
+}
+
+/* ======================= M3U 播放列表 ======================= */
+/*
+    很多播放器按扩展名猜编码: .m3u8 视为 UTF-8,
+    .m3u 视为本地代码页,转码后扩展名要跟着改
+*/
+fn is_m3u_file(path: &Path) -> bool {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| e.eq_ignore_ascii_case("m3u") || e.eq_ignore_ascii_case("m3u8"))
+}
+
+fn fixup_m3u_ext(output: &Path, to_enc: &'static Encoding) -> PathBuf {
+    let want = if to_enc == UTF_8 { "m3u8" } else { "m3u" };
+    output.with_extension(want)
+}
+
+/* ======================= CUE 音轨表 ======================= */
+/*
+    .cue 常见 GBK/Shift_JIS。内容走普通转码,另外把
+    FILE "xxx.ape" 行引用的音频文件一并检查:若磁盘上
+    只存在乱码名(原始字节被当成 Latin-1 读出),按同样
+    的编码规则把它改名成解码后的名字,保证播放器能找到
+*/
+fn is_cue_file(path: &Path) -> bool {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| e.eq_ignore_ascii_case("cue"))
+}
+
+/* 提取 FILE "..." 行里的文件名 */
+fn cue_file_refs(text: &str) -> Vec<String> {
+    text.lines()
+        .filter_map(|line| {
+            let line = line.trim_start();
+            if !line.to_ascii_uppercase().starts_with("FILE") {
+                return None;
             }
-        });
+            let a = line.find('"')?;
+            let b = line[a + 1..].find('"')? + a + 1;
+            Some(line[a + 1..b].to_string())
+        })
+        .collect()
 }
 
-/* ======================= 字体 ======================= */
-fn setup_fonts(ctx: &egui::Context) {
-    let mut fonts = egui::FontDefinitions::default();
-    fonts
-        .font_data
-        .insert("cjk".into(), Arc::new(egui::FontData::from_static(FONT)));
-    fonts
-        .families
-        .get_mut(&egui::FontFamily::Proportional)
-        .unwrap()
-        .insert(0, "cjk".into());
-    fonts
-        .families
-        .get_mut(&egui::FontFamily::Monospace)
-        .unwrap()
-        .insert(0, "cjk".into());
-    ctx.set_fonts(fonts);
+fn repair_cue_refs(dir: &Path, refs: &[String], from_enc: &'static Encoding) -> usize {
+    let mut fixed = 0;
+    for name in refs {
+        let want = dir.join(name);
+        if want.exists() {
+            continue;
+        }
+        let Ok(entries) = std::fs::read_dir(dir) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let got = entry.file_name().to_string_lossy().into_owned();
+            /* 乱码名的每个字符都落在 Latin-1 范围,还原成原始字节 */
+            let bytes: Vec<u8> = got
+                .chars()
+                .filter(|c| (*c as u32) <= 0xFF)
+                .map(|c| c as u8)
+                .collect();
+            if bytes.len() != got.chars().count() {
+                continue;
+            }
+            let (decoded, _) = from_enc.decode_without_bom_handling(&bytes);
+            if decoded == *name && std::fs::rename(entry.path(), &want).is_ok() {
+                fixed += 1;
+                break;
+            }
+        }
+    }
+    fixed
 }
 
-/* ======================= main ======================= */
-fn main() -> Result<(), eframe::Error> {
+/* ======================= Torrent (bencode) ======================= */
+/*
+    老的国内 BT 种子常把 name/path 按 GBK 存放又不带
+    encoding 键。这里解析 bencode,把这些字符串字段重编码成
+    UTF-8 并补上 encoding 键。name/path 都在 info 字典里,
+    改写必然导致 info hash 变化,只能在状态里明确警告
+*/
+enum Bencode {
+    Int(i64),
+    Bytes(Vec<u8>),
+    List(Vec<Bencode>),
+    Dict(Vec<(Vec<u8>, Bencode)>),
+}
+
+fn bdecode(data: &[u8], pos: &mut usize) -> Result<Bencode, String> {
+    match data.get(*pos) {
+        Some(b'i') => {
+            *pos += 1;
+            let end = data[*pos..]
+                .iter()
+                .position(|&b| b == b'e')
+                .ok_or("bad int")?
+                + *pos;
+            let n = std::str::from_utf8(&data[*pos..end])
+                .ok()
+                .and_then(|s| s.parse().ok())
+                .ok_or("bad int")?;
+            *pos = end + 1;
+            Ok(Bencode::Int(n))
+        }
+        Some(b'l') => {
+            *pos += 1;
+            let mut items = Vec::new();
+            while data.get(*pos) != Some(&b'e') {
+                items.push(bdecode(data, pos)?);
+            }
+            *pos += 1;
+            Ok(Bencode::List(items))
+        }
+        Some(b'd') => {
+            *pos += 1;
+            let mut entries = Vec::new();
+            while data.get(*pos) != Some(&b'e') {
+                let key = match bdecode(data, pos)? {
+                    Bencode::Bytes(k) => k,
+                    _ => return Err("bad dict key".into()),
+                };
+                entries.push((key, bdecode(data, pos)?));
+            }
+            *pos += 1;
+            Ok(Bencode::Dict(entries))
+        }
+        Some(b'0'..=b'9') => {
+            let colon = data[*pos..]
+                .iter()
+                .position(|&b| b == b':')
+                .ok_or("bad string")?
+                + *pos;
+            let len: usize = std::str::from_utf8(&data[*pos..colon])
+                .ok()
+                .and_then(|s| s.parse().ok())
+                .ok_or("bad string")?;
+            let start = colon + 1;
+            let bytes = data.get(start..start + len).ok_or("bad string")?.to_vec();
+            *pos = start + len;
+            Ok(Bencode::Bytes(bytes))
+        }
+        _ => Err("bad bencode".into()),
+    }
+}
+
+fn bencode(value: &Bencode, out: &mut Vec<u8>) {
+    match value {
+        Bencode::Int(n) => out.extend_from_slice(format!("i{}e", n).as_bytes()),
+        Bencode::Bytes(b) => {
+            out.extend_from_slice(format!("{}:", b.len()).as_bytes());
+            out.extend_from_slice(b);
+        }
+        Bencode::List(items) => {
+            out.push(b'l');
+            for item in items {
+                bencode(item, out);
+            }
+            out.push(b'e');
+        }
+        Bencode::Dict(entries) => {
+            out.push(b'd');
+            for (k, v) in entries {
+                bencode(&Bencode::Bytes(k.clone()), out);
+                bencode(v, out);
+            }
+            out.push(b'e');
+        }
+    }
+}
+
+/* 把 name/path 一类字符串字段从 from 编码转成 UTF-8 */
+fn reencode_torrent_fields(value: &mut Bencode, from_enc: &'static Encoding, changed: &mut bool) {
+    if let Bencode::Dict(entries) = value {
+        for (key, val) in entries.iter_mut() {
+            let is_name_field = key == b"name" || key == b"publisher" || key == b"comment";
+            let is_path_field = key == b"path";
+
+            if is_name_field && let Bencode::Bytes(bytes) = val {
+                if std::str::from_utf8(bytes).is_err() {
+                    let (s, _) = from_enc.decode_without_bom_handling(bytes);
+                    *bytes = s.into_owned().into_bytes();
+                    *changed = true;
+                }
+            } else if is_path_field && let Bencode::List(items) = val {
+                for item in items {
+                    if let Bencode::Bytes(bytes) = item
+                        && std::str::from_utf8(bytes).is_err()
+                    {
+                        let (s, _) = from_enc.decode_without_bom_handling(bytes);
+                        *bytes = s.into_owned().into_bytes();
+                        *changed = true;
+                    }
+                }
+            } else {
+                reencode_torrent_fields(val, from_enc, changed);
+            }
+        }
+    } else if let Bencode::List(items) = value {
+        for item in items {
+            reencode_torrent_fields(item, from_enc, changed);
+        }
+    }
+}
+
+fn is_torrent_file(path: &Path) -> bool {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| e.eq_ignore_ascii_case("torrent"))
+}
+
+fn transcode_torrent(data: &[u8], from_enc: &'static Encoding) -> Result<(Vec<u8>, bool), String> {
+    let mut pos = 0;
+    let mut root = bdecode(data, &mut pos)?;
+
+    let mut changed = false;
+    reencode_torrent_fields(&mut root, from_enc, &mut changed);
+
+    /* 补上/修正顶层 encoding 键 */
+    if changed && let Bencode::Dict(entries) = &mut root {
+        match entries.iter_mut().find(|(k, _)| k == b"encoding") {
+            Some((_, v)) => *v = Bencode::Bytes(b"UTF-8".to_vec()),
+            None => entries.push((b"encoding".to_vec(), Bencode::Bytes(b"UTF-8".to_vec()))),
+        }
+        entries.sort_by(|(a, _), (b, _)| a.cmp(b));
+    }
+
+    let mut out = Vec::with_capacity(data.len());
+    bencode(&root, &mut out);
+    Ok((out, changed))
+}
+
+/* ======================= HTTP 服务 ======================= */
+/*
+    `codetranser --serve [端口]` 以无界面方式运行:
+    POST /convert?from=gbk&to=utf-8  转码请求体并返回
+    GET  /metrics                    Prometheus 文本格式的计数器
+    编码名用 encoding_rs 的 label 解析
+
+    可选限制参数跟在端口之后:
+    --max-body-mb N     请求体上限 (默认 64)
+    --timeout-secs N    单请求读写超时 (默认 30)
+    --max-concurrent N  并发请求上限 (默认 16)
+*/
+static METRIC_CONVERSIONS: AtomicU64 = AtomicU64::new(0);
+static METRIC_BYTES: AtomicU64 = AtomicU64::new(0);
+static METRIC_ERRORS: AtomicU64 = AtomicU64::new(0);
+static METRIC_LATENCY_MS: AtomicU64 = AtomicU64::new(0);
+
+const DEFAULT_PORT: u16 = 8737;
+
+#[derive(Clone, Copy)]
+struct ServerLimits {
+    max_body: usize,
+    timeout: Duration,
+    max_concurrent: usize,
+}
+
+impl Default for ServerLimits {
+    fn default() -> Self {
+        ServerLimits {
+            max_body: 64 * 1024 * 1024,
+            timeout: Duration::from_secs(30),
+            max_concurrent: 16,
+        }
+    }
+}
+
+impl ServerLimits {
+    /* 解析 --serve 之后剩余的参数, 未知参数直接忽略 */
+    fn from_args(args: impl Iterator<Item = String>) -> Self {
+        let mut limits = ServerLimits::default();
+        let mut args = args;
+        while let Some(flag) = args.next() {
+            let value = args.next().and_then(|v| v.parse::<u64>().ok());
+            match (flag.as_str(), value) {
+                ("--max-body-mb", Some(v)) => limits.max_body = v as usize * 1024 * 1024,
+                ("--timeout-secs", Some(v)) => limits.timeout = Duration::from_secs(v),
+                ("--max-concurrent", Some(v)) => limits.max_concurrent = v.max(1) as usize,
+                _ => {}
+            }
+        }
+        limits
+    }
+}
+
+fn metrics_text() -> String {
+    format!(
+        concat!(
+            "# TYPE codetranser_conversions_total counter\n",
+            "codetranser_conversions_total {}\n",
+            "# TYPE codetranser_bytes_total counter\n",
+            "codetranser_bytes_total {}\n",
+            "# TYPE codetranser_errors_total counter\n",
+            "codetranser_errors_total {}\n",
+            "# TYPE codetranser_latency_milliseconds_total counter\n",
+            "codetranser_latency_milliseconds_total {}\n",
+        ),
+        METRIC_CONVERSIONS.load(Ordering::Relaxed),
+        METRIC_BYTES.load(Ordering::Relaxed),
+        METRIC_ERRORS.load(Ordering::Relaxed),
+        METRIC_LATENCY_MS.load(Ordering::Relaxed),
+    )
+}
+
+fn query_param<'a>(query: &'a str, key: &str) -> Option<&'a str> {
+    query
+        .split('&')
+        .filter_map(|kv| kv.split_once('='))
+        .find(|(k, _)| *k == key)
+        .map(|(_, v)| v)
+}
+
+fn http_response(stream: &mut TcpStream, status: &str, content_type: &str, body: &[u8]) {
+    use std::io::Write;
+    let head = format!(
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        status,
+        content_type,
+        body.len()
+    );
+    stream.write_all(head.as_bytes()).ok();
+    stream.write_all(body).ok();
+}
+
+fn handle_conn(mut stream: TcpStream, limits: ServerLimits) {
+    use std::io::{BufRead, BufReader, Read};
+
+    /* 慢速或挂起的客户端按超时断开, 不无限占用线程 */
+    stream.set_read_timeout(Some(limits.timeout)).ok();
+    stream.set_write_timeout(Some(limits.timeout)).ok();
+
+    let mut reader = BufReader::new(match stream.try_clone() {
+        Ok(s) => s,
+        Err(_) => return,
+    });
+
+    let mut request_line = String::new();
+    if reader.read_line(&mut request_line).is_err() {
+        return;
+    }
+    let mut parts = request_line.split_whitespace();
+    let (method, target) = match (parts.next(), parts.next()) {
+        (Some(m), Some(t)) => (m.to_string(), t.to_string()),
+        _ => return,
+    };
+
+    /* 头部只关心 Content-Length */
+    let mut content_length = 0usize;
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line).is_err() || line.trim().is_empty() {
+            break;
+        }
+        if let Some((k, v)) = line.split_once(':')
+            && k.trim().eq_ignore_ascii_case("content-length")
+        {
+            content_length = v.trim().parse().unwrap_or(0);
+        }
+    }
+
+    let (path, query) = target.split_once('?').unwrap_or((target.as_str(), ""));
+    match (method.as_str(), path) {
+        ("GET", "/metrics") => {
+            http_response(
+                &mut stream,
+                "200 OK",
+                "text/plain; version=0.0.4",
+                metrics_text().as_bytes(),
+            );
+        }
+        ("POST", "/convert") => {
+            /* 读之前先看 Content-Length, 超限直接拒绝而不是整段缓冲 */
+            if content_length > limits.max_body {
+                METRIC_ERRORS.fetch_add(1, Ordering::Relaxed);
+                http_response(
+                    &mut stream,
+                    "413 Payload Too Large",
+                    "text/plain",
+                    format!("body exceeds limit of {} bytes", limits.max_body).as_bytes(),
+                );
+                return;
+            }
+
+            let started = Instant::now();
+            let from = query_param(query, "from").and_then(|l| Encoding::for_label(l.as_bytes()));
+            let to = query_param(query, "to").and_then(|l| Encoding::for_label(l.as_bytes()));
+
+            let (Some(from), Some(to)) = (from, to) else {
+                METRIC_ERRORS.fetch_add(1, Ordering::Relaxed);
+                http_response(
+                    &mut stream,
+                    "400 Bad Request",
+                    "text/plain",
+                    b"unknown encoding label",
+                );
+                return;
+            };
+
+            let mut body = vec![0u8; content_length];
+            if reader.read_exact(&mut body).is_err() {
+                METRIC_ERRORS.fetch_add(1, Ordering::Relaxed);
+                http_response(&mut stream, "400 Bad Request", "text/plain", b"short body");
+                return;
+            }
+
+            let (decoded, _) = from.decode_without_bom_handling(&body);
+            let (encoded, _, _) = to.encode(&decoded);
+
+            METRIC_CONVERSIONS.fetch_add(1, Ordering::Relaxed);
+            METRIC_BYTES.fetch_add(body.len() as u64, Ordering::Relaxed);
+            METRIC_LATENCY_MS.fetch_add(started.elapsed().as_millis() as u64, Ordering::Relaxed);
+            http_response(&mut stream, "200 OK", "application/octet-stream", &encoded);
+        }
+        _ => http_response(&mut stream, "404 Not Found", "text/plain", b"not found"),
+    }
+}
+
+fn run_server(port: u16, limits: ServerLimits) -> std::io::Result<()> {
+    let listener = TcpListener::bind(("127.0.0.1", port))?;
+    eprintln!("codetranser serving on 127.0.0.1:{}", port);
+
+    let active = Arc::new(AtomicUsize::new(0));
+    for mut stream in listener.incoming().flatten() {
+        /* 并发到达上限时立即回 503, 而不是排队堆积连接 */
+        if active.load(Ordering::Relaxed) >= limits.max_concurrent {
+            http_response(
+                &mut stream,
+                "503 Service Unavailable",
+                "text/plain",
+                b"busy",
+            );
+            continue;
+        }
+        active.fetch_add(1, Ordering::Relaxed);
+        let active = Arc::clone(&active);
+        thread::spawn(move || {
+            handle_conn(stream, limits);
+            active.fetch_sub(1, Ordering::Relaxed);
+        });
+    }
+    Ok(())
+}
+
+/* ======================= 网络抓取 ======================= */
+/*
+    "从 URL" 抓远程文本再转码: 朴素的 HTTP 客户端,
+    只支持明文 http:// (老 GB2312 站点基本都是),
+    跟最多 3 次重定向, 返回正文和头里声明的 charset,
+    charset 只是预填来源编码, 用户随时可改
+*/
+fn http_get(url: &str) -> Result<(Vec<u8>, Option<String>), String> {
+    use std::io::{Read, Write};
+
+    let mut url = url.trim().to_string();
+    for _ in 0..4 {
+        if url.starts_with("https://") {
+            return Err("https is not supported, use a plain http:// mirror".into());
+        }
+        let rest = url
+            .strip_prefix("http://")
+            .ok_or_else(|| "only http:// URLs are supported".to_string())?;
+        let (hostport, path) = match rest.split_once('/') {
+            Some((h, p)) => (h.to_string(), format!("/{}", p)),
+            None => (rest.to_string(), "/".to_string()),
+        };
+        let addr = if hostport.contains(':') {
+            hostport.clone()
+        } else {
+            format!("{}:80", hostport)
+        };
+        let mut stream = TcpStream::connect(&addr).map_err(|e| e.to_string())?;
+        stream.set_read_timeout(Some(Duration::from_secs(15))).ok();
+        let req = format!(
+            "GET {} HTTP/1.1\r\nHost: {}\r\nConnection: close\r\nUser-Agent: EncodeConverter\r\n\r\n",
+            path, hostport
+        );
+        stream
+            .write_all(req.as_bytes())
+            .map_err(|e| e.to_string())?;
+
+        let mut raw = Vec::new();
+        stream.read_to_end(&mut raw).map_err(|e| e.to_string())?;
+        let split = raw
+            .windows(4)
+            .position(|w| w == b"\r\n\r\n")
+            .ok_or_else(|| "malformed HTTP response".to_string())?;
+        let head = String::from_utf8_lossy(&raw[..split]).into_owned();
+        let body = raw[split + 4..].to_vec();
+
+        let status: u32 = head
+            .split_whitespace()
+            .nth(1)
+            .and_then(|c| c.parse().ok())
+            .ok_or_else(|| "malformed status line".to_string())?;
+        let header = |name: &str| {
+            head.lines().find_map(|l| {
+                let (k, v) = l.split_once(':')?;
+                k.trim()
+                    .eq_ignore_ascii_case(name)
+                    .then(|| v.trim().to_string())
+            })
+        };
+
+        if (301..=308).contains(&status)
+            && let Some(loc) = header("location")
+        {
+            /* 相对跳转补全成绝对地址再来一轮 */
+            url = if loc.starts_with("http") {
+                loc
+            } else {
+                format!("http://{}{}", hostport, loc)
+            };
+            continue;
+        }
+        if status != 200 {
+            return Err(format!("HTTP {}", status));
+        }
+
+        let body = if header("transfer-encoding").is_some_and(|v| v.eq_ignore_ascii_case("chunked"))
+        {
+            dechunk(&body)
+        } else {
+            body
+        };
+        let charset = header("content-type").and_then(|ct| {
+            ct.split(';').find_map(|part| {
+                part.trim()
+                    .strip_prefix("charset=")
+                    .map(|c| c.trim_matches('"').to_string())
+            })
+        });
+        return Ok((body, charset));
+    }
+    Err("too many redirects".into())
+}
+
+/* 拆 chunked 正文: 十六进制长度行 + 数据块, 0 结束 */
+fn dechunk(body: &[u8]) -> Vec<u8> {
+    let mut out = Vec::new();
+    let mut i = 0;
+    while i < body.len() {
+        let line_end = match body[i..].windows(2).position(|w| w == b"\r\n") {
+            Some(p) => i + p,
+            None => break,
+        };
+        let size_str = String::from_utf8_lossy(&body[i..line_end]);
+        let size = match usize::from_str_radix(size_str.split(';').next().unwrap_or("").trim(), 16)
+        {
+            Ok(n) => n,
+            Err(_) => break,
+        };
+        if size == 0 {
+            break;
+        }
+        let start = line_end + 2;
+        let end = (start + size).min(body.len());
+        out.extend_from_slice(&body[start..end]);
+        i = end + 2;
+    }
+    out
+}
+
+/* URL 最后一段当本地文件名, 取不出来就用兜底名 */
+fn url_file_name(url: &str) -> String {
+    let cut = url.split(['?', '#']).next().unwrap_or(url);
+    let name = cut.trim_end_matches('/').rsplit('/').next().unwrap_or("");
+    if name.is_empty() || name.starts_with("http") {
+        "download.txt".to_string()
+    } else {
+        name.to_string()
+    }
+}
+
+/* ======================= 管道模式 ======================= */
+/*
+    `codetranser --from shift_jis --to utf-8 < in.txt > out.txt`
+    从标准输入流式转码到标准输出, 方便 shell 管道和
+    pre-commit 过滤器使用。整个过程分块进行, 不把文件读进内存
+*/
+fn run_pipe(from: &'static Encoding, to: &'static Encoding) -> std::io::Result<()> {
+    let mut stdin = std::io::stdin().lock();
+    let mut stdout = std::io::stdout().lock();
+    let opts = encodeconverter_core::TranscodeOptions {
+        from,
+        to,
+        write_bom: false,
+    };
+    encodeconverter_core::transcode_reader_to_writer(&mut stdin, &mut stdout, &opts)
+}
+
+/* 解析 --from/--to 两个标签, 标签交给 Encoding::for_label 识别 */
+fn pipe_encodings(
+    mut args: impl Iterator<Item = String>,
+) -> Result<(&'static Encoding, &'static Encoding), String> {
+    let mut from = UTF_8;
+    let mut to = UTF_8;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--from" | "--to" => {
+                let label = args.next().ok_or(format!("{} needs a value", arg))?;
+                let enc = Encoding::for_label(label.as_bytes())
+                    .ok_or(format!("unknown encoding: {}", label))?;
+                if arg == "--from" {
+                    from = enc;
+                } else {
+                    to = enc;
+                }
+            }
+            other => return Err(format!("unknown argument: {}", other)),
+        }
+    }
+    Ok((from, to))
+}
+
+/* ======================= 右键菜单 ======================= */
+/*
+    资源管理器右键 "Convert to UTF-8": --register 用 reg.exe
+    把自己写进 HKCU\Software\Classes\*\shell, 点菜单后以
+    --to-utf8 无界面模式原地转换, 结果弹对话框。
+    两条 reg 命令就够, 不为这个引注册表 crate
+*/
+#[cfg(windows)]
+const SHELL_KEY: &str = r"HKCU\Software\Classes\*\shell\EncodeConverter";
+
+#[cfg(windows)]
+fn reg_run(args: &[&str]) -> Result<(), String> {
+    let out = std::process::Command::new("reg")
+        .args(args)
+        .output()
+        .map_err(|e| e.to_string())?;
+    if out.status.success() {
+        Ok(())
+    } else {
+        Err(String::from_utf8_lossy(&out.stderr).trim().to_string())
+    }
+}
+
+#[cfg(windows)]
+fn register_shell_menu() -> Result<(), String> {
+    let exe = std::env::current_exe().map_err(|e| e.to_string())?;
+    reg_run(&["add", SHELL_KEY, "/ve", "/d", "Convert to UTF-8", "/f"])?;
+    let icon = exe.display().to_string();
+    reg_run(&["add", SHELL_KEY, "/v", "Icon", "/d", &icon, "/f"])?;
+    let key = format!(r"{}\command", SHELL_KEY);
+    let cmd = format!("\"{}\" --to-utf8 \"%1\"", exe.display());
+    reg_run(&["add", &key, "/ve", "/d", &cmd, "/f"])
+}
+
+#[cfg(windows)]
+fn unregister_shell_menu() -> Result<(), String> {
+    reg_run(&["delete", SHELL_KEY, "/f"])
+}
+
+#[cfg(not(windows))]
+fn register_shell_menu() -> Result<(), String> {
+    Err("shell integration is only available on Windows".into())
+}
+
+#[cfg(not(windows))]
+fn unregister_shell_menu() -> Result<(), String> {
+    Err("shell integration is only available on Windows".into())
+}
+
+/* 无界面单文件转换的结果, 文本和 JSON 输出共用 */
+struct CliOutcome {
+    encoding: &'static str,
+    /* 已经是 UTF-8 或像二进制, 没动 */
+    skipped: bool,
+    /* 像是二进制, 跳过原因单独标出来 */
+    binary: bool,
+    /* 解码时被替换成 U+FFFD 的字符数 */
+    lossy: usize,
+}
+
+/* 右键进来的无界面转换: 探测编码, 先落 .bak 再原地改写成无 BOM UTF-8 */
+fn convert_to_utf8(path: &Path, password: &str) -> Result<CliOutcome, String> {
+    /* 压缩包走压缩包管线: 修条目名, 文本条目转成 UTF-8 */
+    if is_zip_file(path) {
+        return convert_zip_to_utf8(path, password);
+    }
+    let data = std::fs::read(path).map_err(|e| e.to_string())?;
+    /* 右键全选难免混进图片压缩包, 像二进制的不碰 */
+    if looks_binary(&data) {
+        return Ok(CliOutcome {
+            encoding: "",
+            skipped: true,
+            binary: true,
+            lossy: 0,
+        });
+    }
+    let enc = detect_encoding_for(path, &data);
+    if enc == UTF_8 && !data.starts_with(&[0xEF, 0xBB, 0xBF]) {
+        return Ok(CliOutcome {
+            encoding: enc.name(),
+            skipped: true,
+            binary: false,
+            lossy: 0,
+        });
+    }
+    let (text, _, had_errors) = enc.decode(&data);
+    let lossy = if had_errors {
+        text.chars().filter(|c| *c == '\u{FFFD}').count()
+    } else {
+        0
+    };
+    let text = text.into_owned();
+    std::fs::copy(path, bak_path(path)).map_err(|e| e.to_string())?;
+    std::fs::write(path, text.as_bytes()).map_err(|e| e.to_string())?;
+    Ok(CliOutcome {
+        encoding: enc.name(),
+        skipped: false,
+        binary: false,
+        lossy,
+    })
+}
+
+/* 压缩包的无界面转换: 条目名编码从解不成 UTF-8 的原始名里探测,
+先落 .bak 再原地重写, 加密压缩包用 --password 给的密码解 */
+fn convert_zip_to_utf8(path: &Path, password: &str) -> Result<CliOutcome, String> {
+    let from = {
+        let file = std::fs::File::open(path).map_err(|e| e.to_string())?;
+        let mut archive =
+            zip::ZipArchive::new(std::io::BufReader::new(file)).map_err(|e| e.to_string())?;
+        let mut raw = Vec::new();
+        for i in 0..archive.len() {
+            let entry = archive.by_index_raw(i).map_err(|e| e.to_string())?;
+            if std::str::from_utf8(entry.name_raw()).is_err() {
+                raw.extend_from_slice(entry.name_raw());
+            }
+        }
+        let enc = detect_encoding(&raw);
+        ENCODINGS.iter().position(|(e, _)| *e == enc).unwrap_or(0)
+    };
+
+    let job = FileJob {
+        input: path.to_path_buf(),
+        output: path.to_path_buf(),
+        from,
+        to: 0, /* UTF-8 */
+        strip_bom: true,
+        write_bom: false,
+        eol: LineEnding::Keep,
+        backup: false,
+        password: password.to_string(),
+        sub_suffix: String::new(),
+        sidecar: false,
+        verify: false,
+        binary: BinaryPolicy::Skip,
+        chain: Vec::new(),
+        lang: Language::En,
+    };
+    std::fs::copy(path, bak_path(path)).map_err(|e| e.to_string())?;
+    transcode_zip(&job)?;
+    Ok(CliOutcome {
+        encoding: ENCODINGS[from].1,
+        skipped: false,
+        binary: false,
+        lossy: 0,
+    })
+}
+
+/* --json: 一行一条记录, 方便接进流水线和 pre-commit 钩子 */
+fn cli_json_record(path: &Path, outcome: &Result<CliOutcome, String>, ms: u128) -> String {
+    let (result, encoding, detail, lossy) = match outcome {
+        Ok(o) if o.binary => ("skipped", o.encoding, "looks binary".to_string(), 0),
+        Ok(o) if o.skipped => ("skipped", o.encoding, String::new(), 0),
+        Ok(o) => ("ok", o.encoding, String::new(), o.lossy),
+        Err(e) => ("error", "", e.clone(), 0),
+    };
+    format!(
+        "{{\"path\": \"{}\", \"encoding\": \"{}\", \"result\": \"{}\", \"detail\": \"{}\", \"lossy_chars\": {}, \"duration_ms\": {}}}",
+        json_escape(&path.display().to_string()),
+        json_escape(encoding),
+        result,
+        json_escape(&detail),
+        lossy,
+        ms
+    )
+}
+
+/* 从右键菜单启动时没有控制台, 结果只能弹窗给用户看 */
+fn shell_toast(summary: &str) {
+    if cfg!(windows) {
+        rfd::MessageDialog::new()
+            .set_title("EncodeConverter")
+            .set_description(summary)
+            .show();
+    }
+}
+
+/* ======================= 启动参数 ======================= */
+/*
+    深链接方式打开界面: 其它工具可以带参数启动,
+    `--mode file --from gbk --to utf-8 --input X --autostart`
+    预先填好界面, --autostart 时直接开始任务。
+    认不出来的参数忽略, 界面照常打开
+*/
+#[derive(Default)]
+struct LaunchArgs {
+    mode: Option<Mode>,
+    from: Option<usize>,
+    to: Option<usize>,
+    input: Option<PathBuf>,
+    output: Option<PathBuf>,
+    autostart: bool,
+}
+
+/* 编码标签 -> ENCODINGS 下标 */
+fn encoding_index(label: &str) -> Option<usize> {
+    let enc = Encoding::for_label(label.as_bytes())?;
+    ENCODINGS.iter().position(|(e, _)| *e == enc)
+}
+
+impl LaunchArgs {
+    fn parse(mut args: impl Iterator<Item = String>) -> Self {
+        let mut out = Self::default();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--mode" => {
+                    out.mode = match args.next().as_deref() {
+                        Some("text") => Some(Mode::Text),
+                        Some("file") => Some(Mode::File),
+                        Some("dir") => Some(Mode::Dir),
+                        Some("names") => Some(Mode::Names),
+                        Some("repair") => Some(Mode::Repair),
+                        Some("playground") => Some(Mode::Playground),
+                        _ => None,
+                    };
+                }
+                "--from" => out.from = args.next().as_deref().and_then(encoding_index),
+                "--to" => out.to = args.next().as_deref().and_then(encoding_index),
+                "--input" => out.input = args.next().map(PathBuf::from),
+                "--output" => out.output = args.next().map(PathBuf::from),
+                "--autostart" => out.autostart = true,
+                _ => {}
+            }
+        }
+        out
+    }
+}
+
+/* ======================= CSV 结构校验 ======================= */
+/*
+    表格文件最怕转码悄悄弄坏分隔符和引号。
+    转完用 csv 解析前后两份内容, 逐行比较列数,
+    对不上就在结果里给出警告
+*/
+fn is_csv_file(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|e| e.eq_ignore_ascii_case("csv") || e.eq_ignore_ascii_case("tsv"))
+}
+
+fn csv_delim(path: &Path) -> u8 {
+    if path
+        .extension()
+        .is_some_and(|e| e.eq_ignore_ascii_case("tsv"))
+    {
+        b'\t'
+    } else {
+        b','
+    }
+}
+
+/* 每条记录的列数 */
+fn csv_shape(text: &str, delim: u8) -> Vec<usize> {
+    csv::ReaderBuilder::new()
+        .has_headers(false)
+        .flexible(true)
+        .delimiter(delim)
+        .from_reader(text.as_bytes())
+        .records()
+        .map(|r| r.map(|rec| rec.len()).unwrap_or(0))
+        .collect()
+}
+
+/* 转码前后列数有出入时返回警告 */
+fn csv_structure_warning(path: &Path, before: &str, after: &str) -> Option<String> {
+    let delim = csv_delim(path);
+    let shape_before = csv_shape(before, delim);
+    let shape_after = csv_shape(after, delim);
+    if shape_before == shape_after {
+        return None;
+    }
+
+    let record = shape_before
+        .iter()
+        .zip(&shape_after)
+        .position(|(a, b)| a != b)
+        .map(|i| i + 1)
+        .unwrap_or_else(|| shape_before.len().min(shape_after.len()) + 1);
+    Some(format!(
+        "warning: CSV structure changed at record {}",
+        record
+    ))
+}
+
+/* ======================= HTML/XML 声明改写 ======================= */
+/*
+    转码 .html/.xml 时把 <meta charset=...> / encoding="..."
+    同步改成目标编码, 否则浏览器按旧声明渲染出乱码
+*/
+fn is_html_file(path: &Path) -> bool {
+    path.extension().is_some_and(|e| {
+        e.eq_ignore_ascii_case("html")
+            || e.eq_ignore_ascii_case("htm")
+            || e.eq_ignore_ascii_case("xhtml")
+    })
+}
+
+fn is_xml_file(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|e| e.eq_ignore_ascii_case("xml"))
+}
+
+/* 把 key= 后面的值换掉, 带不带引号都处理 */
+fn replace_decl_value(text: &mut String, after_key: usize, label: &str) {
+    let bytes = text.as_bytes();
+    let (start, end) = match bytes.get(after_key) {
+        Some(&q @ (b'"' | b'\'')) => {
+            let start = after_key + 1;
+            let end = text[start..].find(q as char).map(|p| start + p);
+            (start, end)
+        }
+        _ => {
+            let end = text[after_key..]
+                .find(['"', '\'', '>', ';', ' ', '/'])
+                .map(|p| after_key + p);
+            (after_key, end)
+        }
+    };
+    if let Some(end) = end {
+        text.replace_range(start..end, label);
+    }
+}
+
+fn rewrite_charset_decl(text: &str, to_enc: &'static Encoding) -> String {
+    let label = to_enc.name();
+    let mut out = text.to_string();
+
+    /* XML 声明只看开头 */
+    let lower = out.to_ascii_lowercase();
+    if lower.starts_with("<?xml")
+        && let Some(pos) = lower.find("encoding=")
+        && pos < lower.find("?>").unwrap_or(0)
+    {
+        replace_decl_value(&mut out, pos + "encoding=".len(), label);
+    }
+
+    /* <meta charset=...> 声明在头部, 搜索范围限制在前 2KB */
+    let lower = out.to_ascii_lowercase();
+    let head = &lower[..lower.len().min(2048)];
+    if let Some(pos) = head.find("charset=") {
+        replace_decl_value(&mut out, pos + "charset=".len(), label);
+    }
+    out
+}
+
+/* ======================= 字幕文件 ======================= */
+/*
+    字幕是这个工具最常见的用途。时间轴和样式块本来就
+    原样过管道, 额外要做的是:
+    - ASS 样式行末尾的 Encoding 字段改成目标编码的代码页
+    - 可选给输出文件名加语言后缀: a.srt -> a.zh.srt
+*/
+fn is_subtitle_file(path: &Path) -> bool {
+    path.extension().is_some_and(|e| {
+        e.eq_ignore_ascii_case("srt")
+            || e.eq_ignore_ascii_case("ass")
+            || e.eq_ignore_ascii_case("ssa")
+    })
+}
+
+fn is_ass_file(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|e| e.eq_ignore_ascii_case("ass") || e.eq_ignore_ascii_case("ssa"))
+}
+
+/* 视频目录配对: 只转同目录下与视频同名的字幕 */
+const VIDEO_EXTS: &[&str] = &[
+    "mp4", "mkv", "avi", "mov", "wmv", "flv", "webm", "ts", "m2ts", "rmvb", "mpg", "mpeg",
+];
+
+fn is_video_file(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|e| VIDEO_EXTS.iter().any(|v| e.eq_ignore_ascii_case(v)))
+}
+
+/* a.zh.srt 也算配 a.mkv: 字幕主干等于视频主干或以其加点开头 */
+fn has_matching_video(sub: &Path) -> bool {
+    let Some(dir) = sub.parent() else {
+        return false;
+    };
+    let Some(stem) = sub.file_stem().map(|s| s.to_string_lossy().to_lowercase()) else {
+        return false;
+    };
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return false;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if !is_video_file(&path) {
+            continue;
+        }
+        if let Some(v) = path.file_stem().map(|s| s.to_string_lossy().to_lowercase())
+            && (stem == v || stem.starts_with(&format!("{}.", v)))
+        {
+            return true;
+        }
+    }
+    false
+}
+
+/* ASS Style 行 Encoding 字段用的 Windows 代码页编号 */
+fn ass_codepage(enc: &'static Encoding) -> u32 {
+    if enc == GBK || enc == GB18030 {
+        134
+    } else if enc == BIG5 {
+        136
+    } else if enc == SHIFT_JIS {
+        128
+    } else if enc == EUC_KR {
+        129
+    } else if enc == WINDOWS_1251 {
+        204
+    } else if enc == WINDOWS_1250 {
+        238
+    } else if enc == WINDOWS_1253 {
+        161
+    } else if enc == WINDOWS_1254 {
+        162
+    } else {
+        1 /* 默认字符集, UTF-8 字幕用这个 */
+    }
+}
+
+/* 把每个 Style: 行的最后一个字段 (Encoding) 换成目标代码页 */
+fn fix_ass_encoding(text: &str, to_enc: &'static Encoding) -> String {
+    let codepage = ass_codepage(to_enc).to_string();
+    let mut out = Vec::new();
+    for line in text.lines() {
+        if line.trim_start().starts_with("Style:")
+            && let Some((head, _)) = line.rsplit_once(',')
+        {
+            out.push(format!("{},{}", head, codepage));
+        } else {
+            out.push(line.to_string());
+        }
+    }
+    let mut joined = out.join("\n");
+    if text.ends_with('\n') {
+        joined.push('\n');
+    }
+    joined
+}
+
+/* a.srt + "zh" -> a.zh.srt */
+fn subtitle_suffixed(path: &Path, suffix: &str) -> PathBuf {
+    let stem = path
+        .file_stem()
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let ext = path
+        .extension()
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    path.with_file_name(format!("{}.{}.{}", stem, suffix, ext))
+}
+
+/* ======================= ZIP 压缩包 ======================= */
+/*
+    老压缩包的条目名多是 CP437/GBK, 解出来是乱码。
+    按来源编码修复条目名, 文本类条目顺带转码,
+    写出一个全 UTF-8 的新压缩包; 加密压缩包要提供密码
+*/
+fn is_zip_file(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|e| e.eq_ignore_ascii_case("zip"))
+}
+
+static ZIP_TEXT_EXTS: &[&str] = &[
+    "txt", "csv", "srt", "ass", "ssa", "cue", "m3u", "m3u8", "html", "htm", "xml", "md", "log",
+    "ini", "nfo", "json",
+];
+
+fn zip_entry_is_text(name: &str) -> bool {
+    name.rsplit_once('.')
+        .is_some_and(|(_, ext)| ZIP_TEXT_EXTS.iter().any(|e| ext.eq_ignore_ascii_case(e)))
+}
+
+fn transcode_zip(job: &FileJob) -> Result<String, String> {
+    use std::io::{Read, Write};
+
+    let (from_enc, _) = ENCODINGS[job.from];
+
+    let file = std::fs::File::open(&job.input).map_err(|e| e.to_string())?;
+    let mut archive =
+        zip::ZipArchive::new(std::io::BufReader::new(file)).map_err(|e| e.to_string())?;
+
+    /* 原地转换时先写临时文件, 成功后再替换 */
+    let in_place = job.input == job.output;
+    let tmp = if in_place {
+        job.output.with_extension("zip.tmp")
+    } else {
+        job.output.clone()
+    };
+    let out = std::fs::File::create(&tmp).map_err(|e| e.to_string())?;
+    let mut writer = zip::ZipWriter::new(std::io::BufWriter::new(out));
+    let options = zip::write::SimpleFileOptions::default();
+
+    let mut renamed = 0;
+    let mut converted = 0;
+    for i in 0..archive.len() {
+        let mut entry = if job.password.is_empty() {
+            archive.by_index(i)
+        } else {
+            archive.by_index_decrypt(i, job.password.as_bytes())
+        }
+        .map_err(|e| e.to_string())?;
+
+        let raw = entry.name_raw().to_vec();
+        let name = match std::str::from_utf8(&raw) {
+            Ok(s) => s.to_string(),
+            Err(_) => {
+                let (s, _) = from_enc.decode_without_bom_handling(&raw);
+                renamed += 1;
+                s.into_owned()
+            }
+        };
+
+        if entry.is_dir() {
+            writer
+                .add_directory(name, options)
+                .map_err(|e| e.to_string())?;
+            continue;
+        }
+
+        let mut content = Vec::new();
+        entry.read_to_end(&mut content).map_err(|e| e.to_string())?;
+
+        /* 内容转码只动文本类条目, 且来源编码解不动就原样保留 */
+        if zip_entry_is_text(&name) {
+            let stripped = strip_bom(&content, from_enc);
+            let (text, had_errors) = from_enc.decode_without_bom_handling(stripped);
+            if !had_errors {
+                let (encoded, _) = encode_idx(job.to, &text);
+                content = encoded;
+                converted += 1;
+            }
+        }
+
+        writer
+            .start_file(name, options)
+            .map_err(|e| e.to_string())?;
+        writer.write_all(&content).map_err(|e| e.to_string())?;
+    }
+    writer.finish().map_err(|e| e.to_string())?;
+    drop(archive);
+
+    if in_place {
+        std::fs::rename(&tmp, &job.output).map_err(|e| e.to_string())?;
+    }
+    Ok(format!(
+        "Done: {} ({} names fixed, {} entries converted)",
+        job.output.display(),
+        renamed,
+        converted
+    ))
+}
+
+/* JSON 字符串转义, inspect 输出用 */
+fn json_escape(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+/*
+    只读检查: 列出条目名和文本内容各自猜出的编码, 打成 JSON,
+    不改动任何文件。拿到可疑压缩包先 inspect 一眼再决定怎么转
+*/
+fn inspect_zip(path: &Path, password: &str) -> Result<String, String> {
+    use std::io::Read;
+
+    let file = std::fs::File::open(path).map_err(|e| e.to_string())?;
+    let mut archive =
+        zip::ZipArchive::new(std::io::BufReader::new(file)).map_err(|e| e.to_string())?;
+
+    let mut out = String::from("[");
+    for i in 0..archive.len() {
+        let (raw, is_dir, encrypted, size) = {
+            let entry = archive.by_index_raw(i).map_err(|e| e.to_string())?;
+            (
+                entry.name_raw().to_vec(),
+                entry.is_dir(),
+                entry.encrypted(),
+                entry.size(),
+            )
+        };
+        let (name, name_enc) = match std::str::from_utf8(&raw) {
+            Ok(s) => (s.to_string(), "UTF-8"),
+            Err(_) => {
+                let enc = detect_encoding(&raw);
+                let (s, _) = enc.decode_without_bom_handling(&raw);
+                (s.into_owned(), enc.name())
+            }
+        };
+
+        /* 加密条目没给密码就读不了内容, 目录和二进制条目也不用猜 */
+        let content_enc =
+            if is_dir || !zip_entry_is_text(&name) || (encrypted && password.is_empty()) {
+                None
+            } else {
+                let mut entry = if encrypted {
+                    archive.by_index_decrypt(i, password.as_bytes())
+                } else {
+                    archive.by_index(i)
+                }
+                .map_err(|e| e.to_string())?;
+                let mut data = Vec::new();
+                (&mut entry)
+                    .take(DETECT_LEN as u64)
+                    .read_to_end(&mut data)
+                    .map_err(|e| e.to_string())?;
+                Some(detect_encoding(&data).name())
+            };
+
+        if i > 0 {
+            out.push(',');
+        }
+        out.push_str(&format!(
+            "\n  {{\"name\": \"{}\", \"name_encoding\": \"{}\", \"content_encoding\": {}, \"size\": {}, \"encrypted\": {}}}",
+            json_escape(&name),
+            name_enc,
+            match content_enc {
+                Some(e) => format!("\"{}\"", e),
+                None => "null".to_string(),
+            },
+            size,
+            encrypted
+        ));
+    }
+    out.push_str("\n]");
+    Ok(out)
+}
+
+/* ======================= 历史记录 ======================= */
+/*
+    每次完成的文件转码都追加到用户目录下的 TSV 日志,
+    界面上的「最近任务」面板支持一键重做和打开输出目录
+*/
+#[derive(Clone)]
+struct HistoryEntry {
+    secs: u64,
+    from: usize,
+    to: usize,
+    input: PathBuf,
+    output: PathBuf,
+    result: String,
+}
+
+const HISTORY_LIMIT: usize = 50;
+
+fn history_path() -> Option<PathBuf> {
+    let home = std::env::var_os("USERPROFILE").or_else(|| std::env::var_os("HOME"))?;
+    Some(PathBuf::from(home).join(".codetranser_history.tsv"))
+}
+
+fn load_history() -> Vec<HistoryEntry> {
+    let Some(path) = history_path() else {
+        return Vec::new();
+    };
+    let Ok(text) = std::fs::read_to_string(path) else {
+        return Vec::new();
+    };
+
+    let mut entries: Vec<HistoryEntry> = text
+        .lines()
+        .filter_map(|line| {
+            let mut cols = line.split('\t');
+            Some(HistoryEntry {
+                secs: cols.next()?.parse().ok()?,
+                from: cols.next()?.parse().ok().filter(|i| *i < ENCODINGS.len())?,
+                to: cols.next()?.parse().ok().filter(|i| *i < ENCODINGS.len())?,
+                input: PathBuf::from(cols.next()?),
+                output: PathBuf::from(cols.next()?),
+                result: cols.next()?.to_string(),
+            })
+        })
+        .collect();
+    entries.reverse();
+    entries.truncate(HISTORY_LIMIT);
+    entries
+}
+
+fn append_history(entry: &HistoryEntry) {
+    use std::io::Write;
+    let Some(path) = history_path() else {
+        return;
+    };
+    let line = format!(
+        "{}\t{}\t{}\t{}\t{}\t{}\n",
+        entry.secs,
+        entry.from,
+        entry.to,
+        entry.input.display(),
+        entry.output.display(),
+        entry.result.replace(['\t', '\n'], " "),
+    );
+    if let Ok(mut f) = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+    {
+        f.write_all(line.as_bytes()).ok();
+    }
+}
+
+fn now_secs() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/* epoch 秒转 "YYYY-MM-DD HH:MM"(UTC),避免引入日期库 */
+fn fmt_timestamp(secs: u64) -> String {
+    let days = (secs / 86400) as i64;
+    let rem = secs % 86400;
+    let (h, min) = (rem / 3600, (rem % 3600) / 60);
+
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let d = doy - (153 * mp + 2) / 5 + 1;
+    let m = if mp < 10 { mp + 3 } else { mp - 9 };
+    let y = yoe + era * 400 + i64::from(m <= 2);
+
+    format!("{:04}-{:02}-{:02} {:02}:{:02}", y, m, d, h, min)
+}
+
+/* ======================= 状态日志 ======================= */
+/*
+    单行 status 只留最后一条消息, 批量任务会丢信息。
+    工作线程发来的每条结果都进带时间戳的日志,
+    界面底部滚动显示, 可按级别过滤、一键复制
+*/
+#[derive(Clone, Copy, PartialEq)]
+enum LogLevel {
+    Info,
+    Warn,
+    Error,
+}
+
+impl LogLevel {
+    fn rank(self) -> u8 {
+        match self {
+            LogLevel::Info => 0,
+            LogLevel::Warn => 1,
+            LogLevel::Error => 2,
+        }
+    }
+
+    fn tag(self) -> &'static str {
+        match self {
+            LogLevel::Info => "INFO",
+            LogLevel::Warn => "WARN",
+            LogLevel::Error => "ERROR",
+        }
+    }
+}
+
+struct LogEntry {
+    secs: u64,
+    level: LogLevel,
+    text: String,
+}
+
+/*
+    状态栏从单条 String 换成带级别的消息列表:
+    警告不会被下一条"完成"顶掉, 用户自己点掉;
+    进行中消息只留最新一条, 任务结束自动消失
+*/
+#[derive(Clone, Copy, PartialEq)]
+enum MsgLevel {
+    Info,
+    Warn,
+    Error,
+    Progress,
+}
+
+impl MsgLevel {
+    fn icon(self) -> &'static str {
+        match self {
+            MsgLevel::Info => "ℹ",
+            MsgLevel::Warn => "⚠",
+            MsgLevel::Error => "✖",
+            MsgLevel::Progress => "⏳",
+        }
+    }
+
+    fn color(self, visuals: &egui::Visuals) -> egui::Color32 {
+        match self {
+            MsgLevel::Info | MsgLevel::Progress => visuals.text_color(),
+            MsgLevel::Warn => egui::Color32::YELLOW,
+            MsgLevel::Error => egui::Color32::LIGHT_RED,
+        }
+    }
+}
+
+impl LogLevel {
+    fn as_msg(self) -> MsgLevel {
+        match self {
+            LogLevel::Info => MsgLevel::Info,
+            LogLevel::Warn => MsgLevel::Warn,
+            LogLevel::Error => MsgLevel::Error,
+        }
+    }
+}
+
+struct StatusMsg {
+    level: MsgLevel,
+    text: String,
+}
+
+/* 留太多条状态栏会长过任务区, 旧的挤掉 */
+const MSG_LIMIT: usize = 8;
+
+/* ======================= 本地化格式 ======================= */
+/*
+    报告和状态栏里的数字也按界面语言排版:
+    中日韩大数用 万/亿 一类的单位, 其余用千分位逗号;
+    时间戳按各语言习惯排
+*/
+fn fmt_count(n: usize, lang: Language) -> String {
+    /* 以万为级的书写习惯, 单位字随语言变 */
+    let myriad = match lang {
+        Language::Zh => Some(("万", "亿")),
+        Language::ZhTw => Some(("萬", "億")),
+        Language::Ja => Some(("万", "億")),
+        Language::Ko => Some(("만", "억")),
+        Language::En | Language::Ru => None,
+    };
+    match myriad {
+        Some((wan, yi)) => {
+            if n >= 100_000_000 {
+                format!("{:.1} {}", n as f64 / 1e8, yi)
+            } else if n >= 10_000 {
+                format!("{:.1} {}", n as f64 / 1e4, wan)
+            } else {
+                n.to_string()
+            }
+        }
+        None => {
+            let s = n.to_string();
+            let mut out = String::with_capacity(s.len() + s.len() / 3);
+            for (i, c) in s.chars().enumerate() {
+                if i > 0 && (s.len() - i).is_multiple_of(3) {
+                    out.push(',');
+                }
+                out.push(c);
+            }
+            out
+        }
+    }
+}
+
+fn fmt_size(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "KB", "MB", "GB", "TB"];
+    let mut v = bytes as f64;
+    let mut unit = 0;
+    while v >= 1024.0 && unit < UNITS.len() - 1 {
+        v /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} B", bytes)
+    } else {
+        format!("{:.1} {}", v, UNITS[unit])
+    }
+}
+
+/* 秒数排成 mm:ss, 超过一小时带小时位 */
+fn fmt_duration(secs: u64) -> String {
+    let (h, m, s) = (secs / 3600, secs % 3600 / 60, secs % 60);
+    if h > 0 {
+        format!("{}:{:02}:{:02}", h, m, s)
+    } else {
+        format!("{:02}:{:02}", m, s)
+    }
+}
+
+fn fmt_timestamp_lang(secs: u64, lang: Language) -> String {
+    let plain = fmt_timestamp(secs);
+    /* 年月日 的写法, 其余语言直接用 ISO 形式 */
+    let ymd = match lang {
+        Language::Zh | Language::ZhTw | Language::Ja => ("年", "月", "日"),
+        Language::Ko => ("년 ", "월 ", "일"),
+        Language::En | Language::Ru => return plain,
+    };
+    /* 2026-08-28 12:30 -> 2026年08月28日 12:30 */
+    let (date, time) = plain.split_once(' ').unwrap_or((plain.as_str(), ""));
+    let p: Vec<&str> = date.split('-').collect();
+    if p.len() == 3 {
+        format!(
+            "{}{}{}{}{}{} {}",
+            p[0], ymd.0, p[1], ymd.1, p[2], ymd.2, time
+        )
+    } else {
+        plain
+    }
+}
+
+/* 在系统文件管理器里显示输出文件 */
+fn open_in_folder(path: &Path) {
+    #[cfg(target_os = "windows")]
+    {
+        std::process::Command::new("explorer")
+            .arg("/select,")
+            .arg(path)
+            .spawn()
+            .ok();
+    }
+    #[cfg(target_os = "macos")]
+    {
+        std::process::Command::new("open")
+            .arg("-R")
+            .arg(path)
+            .spawn()
+            .ok();
+    }
+    #[cfg(all(unix, not(target_os = "macos")))]
+    {
+        if let Some(dir) = path.parent() {
+            std::process::Command::new("xdg-open").arg(dir).spawn().ok();
+        }
+    }
+}
+
+/* 用系统默认程序打开输出文件 */
+fn open_in_editor(path: &Path) {
+    #[cfg(target_os = "windows")]
+    {
+        std::process::Command::new("cmd")
+            .arg("/C")
+            .arg("start")
+            .arg("")
+            .arg(path)
+            .spawn()
+            .ok();
+    }
+    #[cfg(target_os = "macos")]
+    {
+        std::process::Command::new("open").arg(path).spawn().ok();
+    }
+    #[cfg(all(unix, not(target_os = "macos")))]
+    {
+        std::process::Command::new("xdg-open")
+            .arg(path)
+            .spawn()
+            .ok();
+    }
+}
+
+/* ======================= 工作线程消息 ======================= */
+/*
+    工作线程通过通道上报进度,界面据此显示
+    当前正在转码的文件名和百分比
+*/
+enum WorkerMsg {
+    /* 车道编号, 当前文件名, 进度 0.0..=1.0 */
+    Progress(usize, String, f32),
+    /* 本次任务要处理的总字节数, 任务开始时发一次 */
+    TotalBytes(u64, Instant),
+    /* 又处理完多少字节, 带上报时刻, 界面据此算吞吐 */
+    Bytes(u64, Instant),
+    /* 目录模式下单个文件的结果: 级别, 相对路径, 状态 */
+    FileResult(LogLevel, String, String),
+    /* Ask 策略下撞上同名输出的任务, 停到界面排队等答复 */
+    Conflict(Box<FileJob>, String),
+    /* 仅分析模式下单个文件的检查结果 */
+    Analyze(AnalyzeRow),
+    History(LogLevel, HistoryEntry),
+    Done(LogLevel, String),
+}
+
+/*
+    发送结果的同时叫醒界面重画一帧,
+    转码完成立刻显示, 不用等用户动鼠标
+*/
+#[derive(Clone)]
+struct WorkerTx {
+    tx: mpsc::Sender<WorkerMsg>,
+    ctx: Option<egui::Context>,
+    /* 并行车道编号, 进度消息带上它各占一行显示 */
+    lane: usize,
+}
+
+impl WorkerTx {
+    fn send(&self, msg: WorkerMsg) -> Result<(), mpsc::SendError<WorkerMsg>> {
+        let r = self.tx.send(msg);
+        if let Some(ctx) = &self.ctx {
+            ctx.request_repaint();
+        }
+        r
+    }
+}
+
+/* ======================= 文本模式字节表示 ======================= */
+/*
+    文本框里只能放 Unicode,目标编码的字节序列必须用
+    转义形式(\xB0\xA1 或 %B0%A1)表达才有意义。
+    以前把编码结果按 UTF-8 硬读,非 ASCII 目标全是乱码
+*/
+#[derive(Clone, Copy, PartialEq)]
+enum ByteRepr {
+    Text,
+    Hex,
+    Percent,
+    Base64,
+    /* 非 ASCII 字符输出成 &#N; 数字实体 */
+    Entities,
+}
+
+fn bytes_to_repr(bytes: &[u8], repr: ByteRepr, to_enc: &'static Encoding) -> String {
+    match repr {
+        ByteRepr::Text => {
+            let (s, _) = to_enc.decode_without_bom_handling(bytes);
+            s.into_owned()
+        }
+        ByteRepr::Hex => bytes
+            .iter()
+            .map(|&b| {
+                if b.is_ascii_graphic() || b == b' ' || b == b'\n' {
+                    (b as char).to_string()
+                } else {
+                    format!("\\x{:02X}", b)
+                }
+            })
+            .collect(),
+        ByteRepr::Percent => bytes
+            .iter()
+            .map(|&b| {
+                if b.is_ascii_alphanumeric() || matches!(b, b'-' | b'_' | b'.' | b'~') {
+                    (b as char).to_string()
+                } else {
+                    format!("%{:02X}", b)
+                }
+            })
+            .collect(),
+        ByteRepr::Base64 => base64_encode(bytes),
+        ByteRepr::Entities => {
+            let (s, _) = to_enc.decode_without_bom_handling(bytes);
+            s.chars()
+                .map(|c| {
+                    if c.is_ascii() {
+                        c.to_string()
+                    } else {
+                        format!("&#{};", c as u32)
+                    }
+                })
+                .collect()
+        }
+    }
+}
+
+/* 解析 \xNN / %NN 转义,其余字符按 UTF-8 字节原样;
+整段都是裸十六进制对时按对解析 */
+fn parse_escaped_bytes(input: &str) -> Vec<u8> {
+    let compact: String = input.chars().filter(|c| !c.is_whitespace()).collect();
+    if compact.len() >= 4
+        && compact.len().is_multiple_of(2)
+        && compact.bytes().all(|b| b.is_ascii_hexdigit())
+    {
+        return (0..compact.len())
+            .step_by(2)
+            .filter_map(|i| u8::from_str_radix(&compact[i..i + 2], 16).ok())
+            .collect();
+    }
+
+    let mut out = Vec::new();
+    let chars: Vec<char> = input.chars().collect();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let esc = match chars[i] {
+            '\\' if chars.get(i + 1) == Some(&'x') => Some(2),
+            '%' => Some(1),
+            _ => None,
+        };
+        if let Some(skip) = esc
+            && let (Some(h), Some(l)) = (chars.get(i + skip), chars.get(i + skip + 1))
+            && let (Some(h), Some(l)) = (h.to_digit(16), l.to_digit(16))
+        {
+            out.push((h * 16 + l) as u8);
+            i += skip + 2;
+        } else {
+            let mut buf = [0u8; 4];
+            out.extend_from_slice(chars[i].encode_utf8(&mut buf).as_bytes());
+            i += 1;
+        }
+    }
+    out
+}
+
+/* ======================= 智能粘贴识别 ======================= */
+/*
+    粘贴内容像 Base64 / 十六进制 / 百分号编码时,
+    在输入框下方给出「先解码」的提示,省去手动设置
+*/
+const BASE64_ALPHABET: &str = "ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+fn base64_encode(data: &[u8]) -> String {
+    let abc = BASE64_ALPHABET.as_bytes();
+    let mut out = String::with_capacity(data.len().div_ceil(3) * 4);
+    for chunk in data.chunks(3) {
+        let b = [
+            chunk[0],
+            *chunk.get(1).unwrap_or(&0),
+            *chunk.get(2).unwrap_or(&0),
+        ];
+        let n = u32::from_be_bytes([0, b[0], b[1], b[2]]);
+        for (i, shift) in [18u32, 12, 6, 0].into_iter().enumerate() {
+            if i <= chunk.len() {
+                out.push(abc[((n >> shift) & 63) as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+fn base64_decode(input: &str) -> Option<Vec<u8>> {
+    let compact: String = input
+        .chars()
+        .filter(|c| !c.is_whitespace() && *c != '=')
+        .collect();
+
+    let mut out = Vec::with_capacity(compact.len() * 3 / 4);
+    let mut acc = 0u32;
+    let mut bits = 0u32;
+    for c in compact.chars() {
+        let v = BASE64_ALPHABET.find(c)? as u32;
+        acc = (acc << 6) | v;
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            out.push((acc >> bits) as u8);
+        }
+    }
+    Some(out)
+}
+
+fn looks_like_hex(input: &str) -> bool {
+    let compact: String = input.chars().filter(|c| !c.is_whitespace()).collect();
+    compact.len() >= 8
+        && compact.len().is_multiple_of(2)
+        && compact.bytes().all(|b| b.is_ascii_hexdigit())
+}
+
+fn looks_like_percent(input: &str) -> bool {
+    input
+        .as_bytes()
+        .windows(3)
+        .filter(|w| w[0] == b'%' && w[1].is_ascii_hexdigit() && w[2].is_ascii_hexdigit())
+        .count()
+        >= 3
+}
+
+fn looks_like_base64(input: &str) -> bool {
+    let compact: String = input.chars().filter(|c| !c.is_whitespace()).collect();
+    compact.len() >= 16
+        && compact.trim_end_matches('=').len() % 4 != 1
+        && compact
+            .trim_end_matches('=')
+            .chars()
+            .all(|c| BASE64_ALPHABET.contains(c))
+        /* 纯十六进制也满足 Base64 字母表, 交给 hex 分支 */
+        && !looks_like_hex(&compact)
+}
+
+/* ======================= HTML 片段 ======================= */
+/*
+    粘贴的 HTML 自带 <meta charset> 时按声明的编码解码,
+    并把实体 (&amp; / &#NNN; / &#xHH;) 还原成字符
+*/
+fn html_meta_charset(bytes: &[u8]) -> Option<&'static Encoding> {
+    /* 声明都在文件开头, 1KB 足够 */
+    let head = &bytes[..bytes.len().min(1024)];
+    let lower: Vec<u8> = head.iter().map(|b| b.to_ascii_lowercase()).collect();
+
+    let pos = lower
+        .windows(8)
+        .position(|w| w == b"charset=")
+        .map(|p| p + 8)?;
+    let rest = &head[pos..];
+    let rest = rest
+        .strip_prefix(b"\"")
+        .or(rest.strip_prefix(b"'"))
+        .unwrap_or(rest);
+    let end = rest
+        .iter()
+        .position(|b| matches!(b, b'"' | b'\'' | b'>' | b';' | b' ' | b'/'))
+        .unwrap_or(rest.len());
+    Encoding::for_label(&rest[..end])
+}
+
+static HTML_ENTITIES: &[(&str, char)] = &[
+    ("amp", '&'),
+    ("lt", '<'),
+    ("gt", '>'),
+    ("quot", '"'),
+    ("apos", '\''),
+    ("nbsp", '\u{00A0}'),
+    ("copy", '©'),
+    ("reg", '®'),
+    ("hellip", '…'),
+    ("mdash", '—'),
+    ("ndash", '–'),
+    ("ldquo", '“'),
+    ("rdquo", '”'),
+    ("lsquo", '‘'),
+    ("rsquo", '’'),
+];
+
+fn decode_html_entities(text: &str) -> (String, usize) {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    let mut count = 0;
+
+    while let Some(amp) = rest.find('&') {
+        out.push_str(&rest[..amp]);
+        rest = &rest[amp..];
+
+        /* 实体最长也就十来个字符, 找不到分号就按普通 & 处理 */
+        let entity = rest[1..]
+            .find(';')
+            .filter(|&semi| semi <= 10)
+            .map(|semi| &rest[1..1 + semi]);
+
+        let decoded = entity.and_then(|name| {
+            if let Some(hex) = name.strip_prefix("#x").or(name.strip_prefix("#X")) {
+                u32::from_str_radix(hex, 16).ok().and_then(char::from_u32)
+            } else if let Some(dec) = name.strip_prefix('#') {
+                dec.parse().ok().and_then(char::from_u32)
+            } else {
+                HTML_ENTITIES
+                    .iter()
+                    .find(|(n, _)| *n == name)
+                    .map(|(_, c)| *c)
+            }
+        });
+
+        match (decoded, entity) {
+            (Some(c), Some(name)) => {
+                out.push(c);
+                count += 1;
+                rest = &rest[name.len() + 2..];
+            }
+            _ => {
+                out.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    (out, count)
+}
+
+/* ======================= 乱码修复 ======================= */
+/*
+    乱码多半是字节被错误的编码解出来的结果。
+    把文本用「错误编码」还原成字节, 再用「正确编码」解码,
+    对每一对组合打分, 分高的大概率就是原文。
+    候选表和打分都在 encodeconverter-core 里
+*/
+struct RepairCandidate {
+    chain: String,
+    text: String,
+    score: i64,
+}
+
+fn repair_candidates(input: &str) -> Vec<RepairCandidate> {
+    let base = plausibility_score(input);
+    let mut out = Vec::new();
+
+    for &wrong in REPAIR_ENCODINGS {
+        /* 原文编不回去说明这条链不成立 */
+        let (bytes, _, had_errors) = wrong.encode(input);
+        if had_errors {
+            continue;
+        }
+        for &right in REPAIR_ENCODINGS {
+            if std::ptr::eq(wrong, right) {
+                continue;
+            }
+            let (text, _) = right.decode_without_bom_handling(&bytes);
+            if text == input {
+                continue;
+            }
+            let score = plausibility_score(&text);
+            if score > base {
+                out.push(RepairCandidate {
+                    chain: format!("{} -> {}", wrong.name(), right.name()),
+                    text: text.into_owned(),
+                    score,
+                });
+            }
+        }
+    }
+
+    out.sort_by_key(|c| std::cmp::Reverse(c.score));
+    out.truncate(8);
+    out
+}
+
+/* ======================= 文件名转码 ======================= */
+/*
+    Linux 上解开的老压缩包常带 GBK 文件名, 显示成乱码。
+    把目录下的名字按来源编码重新解码后改名为 UTF-8,
+    执行前先预览 旧名 -> 新名, 并写出撤销记录
+*/
+const RENAME_UNDO: &str = ".codetranser_rename_undo.tsv";
+
+/* 取文件名的原始字节: Unix 下名字本来就是字节串,
+其它平台只接受整串都在 Latin-1 范围内的情况 */
+fn name_bytes(name: &std::ffi::OsStr) -> Option<Vec<u8>> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::ffi::OsStrExt;
+        Some(name.as_bytes().to_vec())
+    }
+    #[cfg(not(unix))]
+    {
+        let s = name.to_string_lossy();
+        s.chars()
+            .map(|c| u8::try_from(c as u32).ok())
+            .collect::<Option<Vec<u8>>>()
+    }
+}
+
+/* 旧路径 -> 新路径, 深的排前面, 先改子项再改父目录 */
+fn collect_rename_plan(root: &Path, from_enc: &'static Encoding) -> Vec<(PathBuf, PathBuf)> {
+    let mut plan = Vec::new();
+    let mut stack = vec![root.to_path_buf()];
+
+    while let Some(dir) = stack.pop() {
+        let Ok(entries) = std::fs::read_dir(&dir) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.is_dir() {
+                stack.push(path.clone());
+            }
+            let Some(bytes) = name_bytes(&entry.file_name()) else {
+                continue;
+            };
+            if std::str::from_utf8(&bytes).is_ok() {
+                continue; /* 名字已经是合法 UTF-8 */
+            }
+            let (decoded, had_errors) = from_enc.decode_without_bom_handling(&bytes);
+            if had_errors || decoded.contains(['/', '\\', '\u{FFFD}']) {
+                continue;
+            }
+            plan.push((path.clone(), path.with_file_name(decoded.as_ref())));
+        }
+    }
+
+    plan.sort_by_key(|(old, _)| std::cmp::Reverse(old.components().count()));
+    plan
+}
+
+/* 依次改名并把 旧\t新 追加到撤销记录里 */
+fn apply_rename_plan(root: &Path, plan: &[(PathBuf, PathBuf)]) -> (usize, Vec<String>) {
+    let mut records = Vec::new();
+    let mut done = 0;
+    for (old, new) in plan {
+        if new.exists() || std::fs::rename(old, new).is_err() {
+            continue;
+        }
+        records.push(format!("{}\t{}", old.display(), new.display()));
+        done += 1;
+    }
+    if !records.is_empty() {
+        std::fs::write(root.join(RENAME_UNDO), records.join("\n") + "\n").ok();
+    }
+    (done, records)
+}
+
+/* 按记录逆序把名字改回去, 成功后删除记录文件 */
+fn undo_renames(root: &Path) -> Result<usize, String> {
+    let undo = root.join(RENAME_UNDO);
+    let content = std::fs::read_to_string(&undo).map_err(|e| e.to_string())?;
+
+    let mut done = 0;
+    for line in content.lines().rev() {
+        if let Some((old, new)) = line.split_once('\t')
+            && std::fs::rename(new, old).is_ok()
+        {
+            done += 1;
+        }
+    }
+    std::fs::remove_file(&undo).ok();
+    Ok(done)
+}
+
+/* ======================= 批量分析 ======================= */
+/*
+    「仅分析」扫描目录但不写任何文件:
+    识别编码、BOM、换行风格, 并检查转到目标编码是否有损
+*/
+const DETECT_LEN: usize = 64 * 1024;
+
+fn detect_encoding(data: &[u8]) -> &'static Encoding {
+    encodeconverter_core::detect(data)
+}
+
+/* ======================= 探测规则钩子 ======================= */
+/*
+    站点可以在 ~/.codetranser_detect.tsv 里登记自己的探测规则,
+    每行 "通配符<TAB>编码标签", 例如 "export_*.txt<TAB>gbk"。
+    匹配到的路径跳过通用探测, 直接用规则给的编码;
+    规则启动时读一次, 文件不存在就全部走通用探测
+*/
+fn detect_rules_path() -> Option<PathBuf> {
+    let home = std::env::var_os("USERPROFILE").or_else(|| std::env::var_os("HOME"))?;
+    Some(PathBuf::from(home).join(".codetranser_detect.tsv"))
+}
+
+fn detect_rules() -> &'static Vec<(String, &'static Encoding)> {
+    static RULES: std::sync::OnceLock<Vec<(String, &'static Encoding)>> =
+        std::sync::OnceLock::new();
+    RULES.get_or_init(|| {
+        let Some(path) = detect_rules_path() else {
+            return Vec::new();
+        };
+        let Ok(text) = std::fs::read_to_string(path) else {
+            return Vec::new();
+        };
+        text.lines()
+            .filter_map(|line| {
+                let (pattern, label) = line.split_once('\t')?;
+                let enc = Encoding::for_label(label.trim().as_bytes())?;
+                Some((pattern.trim().to_string(), enc))
+            })
+            .collect()
+    })
+}
+
+/* 规则优先, 没命中再走内容探测 */
+fn detect_encoding_for(path: &Path, data: &[u8]) -> &'static Encoding {
+    let name = path
+        .file_name()
+        .map(|s| s.to_string_lossy())
+        .unwrap_or_default();
+    for (pattern, enc) in detect_rules() {
+        if glob_match(pattern, &name) {
+            return enc;
+        }
+    }
+    detect_encoding(data)
+}
+
+/* ======================= 编码对规则 ======================= */
+/*
+    ~/.codetranser_pairs.tsv 给特定编码对配默认流水线:
+    每行 "源标签<TAB>目标标签<TAB>指令,指令", 指令可用
+    strip_bom / no_strip_bom / bom / no_bom / lf / crlf / cr / keep / backup。
+    例如 "gbk<TAB>utf-8<TAB>strip_bom,lf"。
+    选中对应编码对时自动套用, 之后手动改选项就是覆盖
+*/
+struct PairRule {
+    from: usize,
+    to: usize,
+    strip_bom: Option<bool>,
+    write_bom: Option<bool>,
+    eol: Option<LineEnding>,
+    backup: Option<bool>,
+}
+
+fn pair_rules_path() -> Option<PathBuf> {
+    let home = std::env::var_os("USERPROFILE").or_else(|| std::env::var_os("HOME"))?;
+    Some(PathBuf::from(home).join(".codetranser_pairs.tsv"))
+}
+
+fn pair_rules() -> &'static Vec<PairRule> {
+    static RULES: std::sync::OnceLock<Vec<PairRule>> = std::sync::OnceLock::new();
+    RULES.get_or_init(|| {
+        let Some(path) = pair_rules_path() else {
+            return Vec::new();
+        };
+        let Ok(text) = std::fs::read_to_string(path) else {
+            return Vec::new();
+        };
+        text.lines()
+            .filter_map(|line| {
+                let f: Vec<&str> = line.split('\t').collect();
+                if f.len() != 3 {
+                    return None;
+                }
+                let mut rule = PairRule {
+                    from: encoding_index(f[0].trim())?,
+                    to: encoding_index(f[1].trim())?,
+                    strip_bom: None,
+                    write_bom: None,
+                    eol: None,
+                    backup: None,
+                };
+                for step in f[2].split(',') {
+                    match step.trim() {
+                        "strip_bom" => rule.strip_bom = Some(true),
+                        "no_strip_bom" => rule.strip_bom = Some(false),
+                        "bom" => rule.write_bom = Some(true),
+                        "no_bom" => rule.write_bom = Some(false),
+                        "lf" | "crlf" | "cr" | "keep" => rule.eol = Some(eol_from_tag(step.trim())),
+                        "backup" => rule.backup = Some(true),
+                        /* 认不得的指令跳过, 别整行作废 */
+                        _ => {}
+                    }
+                }
+                Some(rule)
+            })
+            .collect()
+    })
+}
+
+fn pair_rule_for(from: usize, to: usize) -> Option<&'static PairRule> {
+    pair_rules().iter().find(|r| r.from == from && r.to == to)
+}
+
+/* ======================= 项目数据库 ======================= */
+/*
+    根目录下的 .encodeconverter.toml 记每个文件转换后的
+    编码和内容指纹: 增量模式跳过指纹没变的文件,
+    指纹变了且又探测出旧编码的, 标记成"回退"再转一遍。
+    [[file]] 一节一个文件, 行式读写, 不为这个引 TOML 库
+*/
+struct DbEntry {
+    encoding: String,
+    fnv: u64,
+}
+
+fn project_db_path(root: &Path) -> PathBuf {
+    root.join(".encodeconverter.toml")
+}
+
+fn load_project_db(root: &Path) -> std::collections::HashMap<String, DbEntry> {
+    let mut map = std::collections::HashMap::new();
+    let Ok(text) = std::fs::read_to_string(project_db_path(root)) else {
+        return map;
+    };
+    let mut path: Option<String> = None;
+    let mut encoding = String::new();
+    let mut fnv = 0u64;
+    let flush = |path: &mut Option<String>,
+                 encoding: &mut String,
+                 fnv: &mut u64,
+                 map: &mut std::collections::HashMap<String, DbEntry>| {
+        if let Some(p) = path.take() {
+            map.insert(
+                p,
+                DbEntry {
+                    encoding: std::mem::take(encoding),
+                    fnv: std::mem::take(fnv),
+                },
+            );
+        }
+    };
+    for line in text.lines() {
+        let line = line.trim();
+        if line == "[[file]]" {
+            flush(&mut path, &mut encoding, &mut fnv, &mut map);
+        } else if let Some((key, value)) = line.split_once('=') {
+            let value = value.trim().trim_matches('"');
+            match key.trim() {
+                "path" => path = Some(value.to_string()),
+                "encoding" => encoding = value.to_string(),
+                "fnv1a64" => fnv = u64::from_str_radix(value, 16).unwrap_or(0),
+                _ => {}
+            }
+        }
+    }
+    flush(&mut path, &mut encoding, &mut fnv, &mut map);
+    map
+}
+
+/* 按路径排序写回, 改动了哪个文件 diff 一眼可见 */
+fn save_project_db(root: &Path, map: &std::collections::HashMap<String, DbEntry>) {
+    let mut keys: Vec<&String> = map.keys().collect();
+    keys.sort();
+    let mut out = String::from("# EncodeConverter incremental database; delete to reconvert all\n");
+    for key in keys {
+        let e = &map[key];
+        out.push_str(&format!(
+            "\n[[file]]\npath = \"{}\"\nencoding = \"{}\"\nfnv1a64 = \"{:016x}\"\n",
+            key.replace('\\', "/"),
+            e.encoding,
+            e.fnv
+        ));
+    }
+    std::fs::write(project_db_path(root), out).ok();
+}
+
+fn eol_style(data: &[u8]) -> &'static str {
+    let mut crlf = 0usize;
+    let mut lf = 0usize;
+    let mut cr = 0usize;
+    let mut i = 0;
+    while i < data.len() {
+        match data[i] {
+            b'\r' if data.get(i + 1) == Some(&b'\n') => {
+                crlf += 1;
+                i += 1;
+            }
+            b'\r' => cr += 1,
+            b'\n' => lf += 1,
+            _ => {}
+        }
+        i += 1;
+    }
+    match (crlf > 0, lf > 0, cr > 0) {
+        (true, false, false) => "CRLF",
+        (false, true, false) => "LF",
+        (false, false, true) => "CR",
+        (false, false, false) => "-",
+        _ => "Mixed",
+    }
+}
+
+struct AnalyzeRow {
+    name: String,
+    encoding: &'static str,
+    bom: bool,
+    eol: &'static str,
+    lossy: bool,
+    /* 解码后的头一行, 探测错了一眼就能看出来 */
+    snippet: String,
+}
+
+/* 取第一个非空行, 控制字符换成占位, 截短到够看 */
+fn snippet_of(text: &str) -> String {
+    const SNIPPET_CHARS: usize = 60;
+    let line = text
+        .lines()
+        .find(|l| !l.trim().is_empty())
+        .unwrap_or_default();
+    let mut out: String = line
+        .trim()
+        .chars()
+        .take(SNIPPET_CHARS)
+        .map(|c| if c.is_control() { '\u{FFFD}' } else { c })
+        .collect();
+    if line.trim().chars().count() > SNIPPET_CHARS {
+        out.push('\u{2026}');
+    }
+    out
+}
+
+fn analyze_file(path: &Path, rel: &Path, to: usize) -> Option<AnalyzeRow> {
+    let (to_enc, _) = ENCODINGS[to];
+    let mut data = std::fs::read(path).ok()?;
+    data.truncate(DETECT_LEN);
+
+    let enc = detect_encoding_for(path, &data);
+    let bom = bom_of(enc).is_some_and(|b| data.starts_with(b));
+    let (decoded, _) = enc.decode_without_bom_handling(strip_bom(&data, enc));
+    /* UTF-16/32 目标什么都编得出, 不算有损 */
+    let lossy = !is_manual_target(to) && to_enc.encode(&decoded).2;
+
+    Some(AnalyzeRow {
+        name: rel.display().to_string(),
+        encoding: enc.name(),
+        bom,
+        eol: eol_style(&data),
+        lossy,
+        snippet: snippet_of(&decoded),
+    })
+}
+
+fn analyze_csv(rows: &[AnalyzeRow]) -> String {
+    let quote = |s: &str| {
+        if s.contains([',', '"']) {
+            format!("\"{}\"", s.replace('"', "\"\""))
+        } else {
+            s.to_string()
+        }
+    };
+    let mut out = String::from("file,encoding,bom,eol,lossy,snippet\n");
+    for row in rows {
+        out.push_str(&format!(
+            "{},{},{},{},{},{}\n",
+            quote(&row.name),
+            row.encoding,
+            row.bom,
+            row.eol,
+            row.lossy,
+            quote(&row.snippet)
+        ));
+    }
+    out
+}
+
+/* ======================= 剪贴板 ======================= */
+/*
+    egui 只提供复制,读取要走 arboard;
+    失败(无显示服务等)时静默返回 None
+*/
+fn clipboard_text() -> Option<String> {
+    arboard::Clipboard::new().ok()?.get_text().ok()
+}
+
+fn set_clipboard_text(text: &str) {
+    if let Ok(mut clip) = arboard::Clipboard::new() {
+        clip.set_text(text).ok();
+    }
+}
+
+/* 资源管理器里复制的文件;拿不到文件列表时
+退回解析文本形式的路径 (file:// 或纯路径按行) */
+fn clipboard_files() -> Vec<PathBuf> {
+    if let Ok(mut clip) = arboard::Clipboard::new()
+        && let Ok(list) = clip.get().file_list()
+        && !list.is_empty()
+    {
+        return list;
+    }
+
+    clipboard_text()
+        .unwrap_or_default()
+        .lines()
+        .map(|l| l.trim().strip_prefix("file://").unwrap_or(l.trim()))
+        .map(PathBuf::from)
+        .filter(|p| p.exists())
+        .collect()
+}
+
+/* ======================= 转码逻辑 ======================= */
+/* 文本模式的全部选项, 也用作实时转码的防抖比较 */
+#[derive(Clone, Copy, PartialEq)]
+struct TextOpts {
+    from: usize,
+    to: usize,
+    eol: LineEnding,
+    in_escaped: bool,
+    in_base64: bool,
+    as_html: bool,
+    repr: ByteRepr,
+}
+
+/* 哪些处理步骤真的改了内容, 转完后报给用户看 */
+#[derive(Default)]
+struct TextReport {
+    entities: usize,
+    eol: usize,
+    replaced: usize,
+}
+
+impl TextReport {
+    fn summary(&self, as_html: bool, eol: LineEnding, lang: Language) -> String {
+        let mut parts = Vec::new();
+        if as_html {
+            parts.push(format!(
+                "{}: {}",
+                t("rep_entities", lang),
+                fmt_count(self.entities, lang)
+            ));
+        }
+        if eol != LineEnding::Keep {
+            parts.push(format!(
+                "{}: {}",
+                t("rep_eol", lang),
+                fmt_count(self.eol, lang)
+            ));
+        }
+        parts.push(format!(
+            "{}: {}",
+            t("rep_replaced", lang),
+            fmt_count(self.replaced, lang)
+        ));
+        parts.join(" · ")
+    }
+}
+
+/* 各种换行序列的出现次数: (crlf, 单独 lf, 单独 cr) */
+fn count_eols(text: &str) -> (usize, usize, usize) {
+    let bytes = text.as_bytes();
+    let (mut crlf, mut lf, mut cr) = (0, 0, 0);
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\r' if bytes.get(i + 1) == Some(&b'\n') => {
+                crlf += 1;
+                i += 1;
+            }
+            b'\r' => cr += 1,
+            b'\n' => lf += 1,
+            _ => {}
+        }
+        i += 1;
+    }
+    (crlf, lf, cr)
+}
+
+fn transcode_text(input: &str, opts: TextOpts) -> (String, TextReport) {
+    let mut report = TextReport::default();
+    let (to_enc, _) = ENCODINGS[opts.to];
+
+    /* 输入要么是普通 Unicode 文本,要么是来源编码包在
+    Base64 / 转义形式里的字节 */
+    let bytes = if opts.in_base64 {
+        base64_decode(input)
+    } else if opts.in_escaped {
+        Some(parse_escaped_bytes(input))
+    } else {
+        None
+    };
+
+    let unicode = match bytes {
+        Some(bytes) => {
+            /* HTML 片段里自带的 charset 声明优先于选择的来源编码 */
+            match opts.as_html.then(|| html_meta_charset(&bytes)).flatten() {
+                Some(enc) => enc.decode_without_bom_handling(&bytes).0.into_owned(),
+                None => decode_idx(opts.from, &bytes),
+            }
+        }
+        None => input.to_string(),
+    };
+
+    let unicode = if opts.as_html {
+        let (decoded, entities) = decode_html_entities(&unicode);
+        report.entities = entities;
+        decoded
+    } else {
+        unicode
+    };
+
+    let (crlf, lf, cr) = count_eols(&unicode);
+    report.eol = match opts.eol {
+        LineEnding::Keep => 0,
+        LineEnding::Lf => crlf + cr,
+        LineEnding::Crlf => lf + cr,
+        LineEnding::Cr => crlf + lf,
+    };
+
+    let unicode = normalize_eol(&unicode, opts.eol);
+    let (encoded, _) = encode_idx(opts.to, &unicode);
+
+    /* encoding_rs 对编不出来的字符写入 &#N; 数字引用,
+    数多出来的引用就是替换次数 */
+    let refs_in = unicode.matches("&#").count();
+    let refs_out = encoded.windows(2).filter(|w| w == b"&#").count();
+    report.replaced = refs_out.saturating_sub(refs_in);
+
+    (bytes_to_repr(&encoded, opts.repr, to_enc), report)
+}
+
+/* ======================= 会话解码缓存 ======================= */
+/*
+    解码过一次的文件在本次会话里缓存解码结果,
+    换目标编码、BOM 或换行选项反复尝试时不用每次
+    重读重解码 —— 几百 MB 的输入上很明显。
+    源文件路径、from 编码或修改时间变了就失效
+*/
+struct DecodeCache {
+    path: PathBuf,
+    mtime: Option<std::time::SystemTime>,
+    from: usize,
+    text: std::rc::Rc<String>,
+}
+
+/* ======================= 转换前后对比 ======================= */
+/*
+    文件模式里点「开始」先生成对比预览,
+    确认之后才真正写盘
+*/
+const COMPARE_LINES: usize = 200;
+const COMPARE_LEN: usize = 128 * 1024;
+/* 丢失字符最多展示这么多个例子 */
+const LOST_SAMPLES: usize = 12;
+
+struct PendingConvert {
+    input: PathBuf,
+    output: PathBuf,
+    before: String,
+    after: String,
+    /* 整个文件会丢多少字符, 确认提示用 */
+    lost_total: usize,
+    lost_samples: Vec<char>,
+}
+
+fn build_compare(input: &Path, output: &Path, text: &str, to: usize) -> Option<PendingConvert> {
+    let (to_enc, _) = ENCODINGS[to];
+
+    let mut before = String::new();
+    for line in text.lines().take(COMPARE_LINES) {
+        if before.len() + line.len() > COMPARE_LEN {
+            break;
+        }
+        if !before.is_empty() {
+            before.push('\n');
+        }
+        before.push_str(line);
+    }
+
+    /* 逐字符试编码, 编不出来的在右侧标成替换符;
+    UTF-16/32 目标什么都编得出, 直接照抄 */
+    let mut after = String::with_capacity(before.len());
+    if is_manual_target(to) {
+        after = before.clone();
+    } else {
+        let mut buf = [0u8; 4];
+        for c in before.chars() {
+            let (_, _, had_errors) = to_enc.encode(c.encode_utf8(&mut buf));
+            if had_errors {
+                after.push('\u{FFFD}');
+            } else {
+                after.push(c);
+            }
+        }
+    }
+
+    /* 全文再过一遍: 每个不同的字符只试编一次,
+    大文件上也远比逐字符严格编码快 */
+    let mut lost_total = 0;
+    let mut lost_samples = Vec::new();
+    if !is_manual_target(to) {
+        let mut good = std::collections::HashSet::new();
+        let mut bad = std::collections::HashSet::new();
+        let mut buf = [0u8; 4];
+        for c in text.chars() {
+            if good.contains(&c) {
+                continue;
+            }
+            if bad.contains(&c) {
+                lost_total += 1;
+                continue;
+            }
+            let (_, _, had_errors) = to_enc.encode(c.encode_utf8(&mut buf));
+            if had_errors {
+                bad.insert(c);
+                if lost_samples.len() < LOST_SAMPLES {
+                    lost_samples.push(c);
+                }
+                lost_total += 1;
+            } else {
+                good.insert(c);
+            }
+        }
+    }
+
+    Some(PendingConvert {
+        input: input.to_path_buf(),
+        output: output.to_path_buf(),
+        before,
+        after,
+        lost_total,
+        lost_samples,
+    })
+}
+
+/* ======================= 二进制检测 ======================= */
+/*
+    把 PNG 当文本转一遍数据就没了。NUL 或控制字节
+    比例太高就当二进制看待, 按策略跳过/询问/强转;
+    来源是 UTF-16/32 时 NUL 属正常, 不做此检查
+*/
+#[derive(Clone, Copy, PartialEq)]
+enum BinaryPolicy {
+    Skip,
+    Ask,
+    Force,
+}
+
+fn looks_binary(data: &[u8]) -> bool {
+    let sample = &data[..data.len().min(DETECT_LEN)];
+    if sample.is_empty() {
+        return false;
+    }
+    if sample.contains(&0) {
+        return true;
+    }
+    let weird = sample
+        .iter()
+        .filter(|b| matches!(**b, 0x01..=0x08 | 0x0B | 0x0E..=0x1F | 0x7F))
+        .count();
+    weird * 10 > sample.len()
+}
+
+fn binary_check_applies(from: usize) -> bool {
+    !matches!(
+        ENCODINGS[from].1,
+        "UTF-16LE" | "UTF-16BE" | "UTF-32LE" | "UTF-32BE"
+    )
+}
+
+/* 小于这个大小的文件在批量任务里走快车道 */
+const SMALL_FILE_LIMIT: u64 = 256 * 1024;
+
+/* 窗口比这窄就切到紧凑布局, 够半屏贴靠用 */
+const NARROW_WIDTH: f32 = 720.0;
+
+/* 一次文件转码任务的全部参数 */
+/* 批量任务表的一项: 任务, 报告名, 增量库要回写的 (根序号, 键) */
+type BatchItem = (FileJob, String, Option<(usize, String)>);
+
+#[derive(Clone)]
+struct FileJob {
+    input: PathBuf,
+    output: PathBuf,
+    from: usize,
+    to: usize,
+    strip_bom: bool,
+    write_bom: bool,
+    eol: LineEnding,
+    backup: bool,
+    /* 仅加密 ZIP 使用, 空串表示无密码 */
+    password: String,
+    /* 字幕输出的语言后缀, 空串表示不加 */
+    sub_suffix: String,
+    /* 在输出旁边写 .meta.json 留痕 */
+    sidecar: bool,
+    /* 写完回读输出并解码核对, 防静默丢字和写坏 */
+    verify: bool,
+    /* 疑似二进制文件的处理策略 */
+    binary: BinaryPolicy,
+    /* 来源编码退路链, 空表示只用 from */
+    chain: Vec<usize>,
+    /* 错误消息用发起任务时的界面语言渲染 */
+    lang: Language,
+}
+
+/* ======================= 转码错误 ======================= */
+/*
+    状态栏消息不再直接贴 io::Error 原文: 错误分好类,
+    经 t() 本地化, 带上出错的路径和字节偏移。
+    解码错和无法映射不会中止转换, 只并入结果里提醒
+*/
+enum TranscodeError {
+    Io(PathBuf, std::io::Error),
+    Permission(PathBuf),
+    /* 来源解码有错, 附第一个坏字节的偏移 */
+    Decode(PathBuf, usize),
+    /* 目标编码表示不了的字符个数 */
+    Unmappable(PathBuf, usize),
+}
+
+impl TranscodeError {
+    fn from_io(path: &Path, e: std::io::Error) -> Self {
+        if e.kind() == std::io::ErrorKind::PermissionDenied {
+            Self::Permission(path.to_path_buf())
+        } else {
+            Self::Io(path.to_path_buf(), e)
+        }
+    }
+
+    fn message(&self, lang: Language) -> String {
+        match self {
+            Self::Io(p, e) => format!("{} {}: {}", t("err_io", lang), p.display(), e),
+            Self::Permission(p) => format!("{} {}", t("err_perm", lang), p.display()),
+            Self::Decode(p, off) => {
+                format!("{} {} (byte {})", t("err_decode", lang), p.display(), off)
+            }
+            Self::Unmappable(p, n) => {
+                format!("{}: {} {}", p.display(), n, t("err_unmap", lang))
+            }
+        }
+    }
+}
+
+/* 重新严格解码一遍, 找出第一个解不动的字节偏移 */
+fn first_decode_error(enc: &'static Encoding, data: &[u8]) -> Option<usize> {
+    let mut decoder = enc.new_decoder_without_bom_handling();
+    let mut out = String::with_capacity(8192);
+    let mut read_total = 0;
+    let mut src = data;
+    loop {
+        let (res, read) = decoder.decode_to_string_without_replacement(src, &mut out, true);
+        read_total += read;
+        src = &src[read..];
+        match res {
+            encoding_rs::DecoderResult::InputEmpty => return None,
+            encoding_rs::DecoderResult::OutputFull => out.clear(),
+            encoding_rs::DecoderResult::Malformed(len, _) => {
+                return Some(read_total.saturating_sub(len as usize));
+            }
+        }
+    }
+}
+
+/* 原文件的 FNV-1a 哈希, 事后审计核对用; 不追求防碰撞 */
+fn fnv1a64(data: &[u8]) -> u64 {
+    let mut h: u64 = 0xcbf2_9ce4_8422_2325;
+    for b in data {
+        h ^= *b as u64;
+        h = h.wrapping_mul(0x0100_0000_01b3);
+    }
+    h
+}
+
+/*
+    可选的 .meta.json 旁记: 原编码、BOM、换行、原文件哈希
+    和这次用的转换选项都写进去, 需要反向转换或者审计时有据可查。
+    写不进去不影响转码本身, 失败静默忽略
+*/
+fn write_sidecar(job: &FileJob, output: &Path, data: &[u8]) {
+    let (_, from_label) = ENCODINGS[job.from];
+    let (_, to_label) = ENCODINGS[job.to];
+    let had_bom = bom_of_idx(job.from).is_some_and(|b| data.starts_with(b));
+    let meta = format!(
+        concat!(
+            "{{\n",
+            "  \"source\": \"{}\",\n",
+            "  \"source_encoding\": \"{}\",\n",
+            "  \"source_bom\": {},\n",
+            "  \"source_eol\": \"{}\",\n",
+            "  \"source_bytes\": {},\n",
+            "  \"source_fnv1a64\": \"{:016x}\",\n",
+            "  \"target_encoding\": \"{}\",\n",
+            "  \"strip_bom\": {},\n",
+            "  \"write_bom\": {},\n",
+            "  \"eol\": \"{}\",\n",
+            "  \"converted_at\": {}\n",
+            "}}\n"
+        ),
+        json_escape(&job.input.display().to_string()),
+        from_label,
+        had_bom,
+        eol_style(data),
+        data.len(),
+        fnv1a64(data),
+        to_label,
+        job.strip_bom,
+        job.write_bom,
+        eol_tag(job.eol),
+        now_secs()
+    );
+    let mut p = output.as_os_str().to_owned();
+    p.push(".meta.json");
+    std::fs::write(PathBuf::from(p), meta).ok();
+}
+
+/* 旁记解析出来的回转参数 */
+struct SidecarMeta {
+    /* 原编码, 回转时写回去的目标 */
+    from: usize,
+    bom: bool,
+    eol: LineEnding,
+    fnv: Option<u64>,
+    /* 迁移后的编码, 回转时按它解码 */
+    to: usize,
+}
+
+/* 标签 -> ENCODINGS 下标, 旁记里存的就是表里的标签 */
+fn label_index(label: &str) -> Option<usize> {
+    ENCODINGS
+        .iter()
+        .position(|(_, l)| l.eq_ignore_ascii_case(label))
+}
+
+/* 自己写的格式自己读: 一行一个键值, 用不着完整的 JSON 解析 */
+fn parse_sidecar(text: &str) -> Option<SidecarMeta> {
+    let mut from = None;
+    let mut to = None;
+    let mut bom = false;
+    let mut eol = LineEnding::Keep;
+    let mut fnv = None;
+    for line in text.lines() {
+        let line = line.trim().trim_end_matches(',');
+        let Some((k, v)) = line.split_once(':') else {
+            continue;
+        };
+        let (k, v) = (k.trim().trim_matches('"'), v.trim().trim_matches('"'));
+        match k {
+            "source_encoding" => from = label_index(v),
+            "source_bom" => bom = v == "true",
+            "source_eol" => {
+                eol = match v {
+                    "CRLF" => LineEnding::Crlf,
+                    "LF" => LineEnding::Lf,
+                    "CR" => LineEnding::Cr,
+                    _ => LineEnding::Keep,
+                }
+            }
+            "source_fnv1a64" => fnv = u64::from_str_radix(v, 16).ok(),
+            "target_encoding" => to = label_index(v),
+            _ => {}
+        }
+    }
+    Some(SidecarMeta {
+        from: from?,
+        bom,
+        eol,
+        fnv,
+        to: to?,
+    })
+}
+
+/* 按旁记把文件转回原编码/BOM/换行; 回传是否和原文件逐字节一致 */
+fn revert_with_sidecar(meta_path: &Path) -> Result<(PathBuf, bool), String> {
+    let text = std::fs::read_to_string(meta_path).map_err(|e| e.to_string())?;
+    let meta =
+        parse_sidecar(&text).ok_or_else(|| format!("bad sidecar: {}", meta_path.display()))?;
+    let file = meta_path
+        .to_string_lossy()
+        .strip_suffix(".meta.json")
+        .map(PathBuf::from)
+        .ok_or_else(|| format!("bad sidecar: {}", meta_path.display()))?;
+
+    let data = std::fs::read(&file).map_err(|e| e.to_string())?;
+    let decoded = decode_idx(meta.to, strip_bom_idx(&data, meta.to));
+    let decoded = normalize_eol(&decoded, meta.eol);
+    let (encoded, _) = encode_idx(meta.from, &decoded);
+    let mut out = Vec::with_capacity(encoded.len() + 4);
+    if meta.bom
+        && let Some(bom) = bom_of_idx(meta.from)
+    {
+        out.extend_from_slice(bom);
+    }
+    out.extend_from_slice(&encoded);
+    std::fs::write(&file, &out).map_err(|e| e.to_string())?;
+
+    let exact = meta.fnv.is_none_or(|h| fnv1a64(&out) == h);
+    Ok((file, exact))
+}
+
+/* 原地转换时的备份文件名: a.txt -> a.txt.bak */
+fn bak_path(path: &Path) -> PathBuf {
+    let name = path
+        .file_name()
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    path.with_file_name(format!("{}.bak", name))
+}
+
+/* 退路链: 按顺序试, 返回第一个解不出替换符的编码 */
+fn chain_pick(chain: &[usize], data: &[u8], strip: bool) -> Option<usize> {
+    chain.iter().copied().find(|&idx| {
+        let bytes = if strip {
+            strip_bom_idx(data, idx)
+        } else {
+            data
+        };
+        !decode_idx(idx, bytes).contains('\u{FFFD}')
+    })
+}
+
+fn transcode_file(mut job: FileJob, tx: &WorkerTx) -> (LogLevel, String) {
+    let name = job
+        .input
+        .file_name()
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    tx.send(WorkerMsg::Progress(tx.lane, name.clone(), 0.0))
+        .ok();
+
+    /* .zip 压缩包单独处理:修条目名, 文本条目顺带转码 */
+    if is_zip_file(&job.input) {
+        return match transcode_zip(&job) {
+            Ok(msg) => (LogLevel::Info, msg),
+            Err(e) => (LogLevel::Error, e),
+        };
+    }
+
+    let data = match std::fs::read(&job.input) {
+        Ok(v) => v,
+        Err(e) => {
+            return (
+                LogLevel::Error,
+                TranscodeError::from_io(&job.input, e).message(job.lang),
+            );
+        }
+    };
+    tx.send(WorkerMsg::Progress(tx.lane, name.clone(), 0.3))
+        .ok();
+
+    let (from_enc, _) = ENCODINGS[job.from];
+    let (to_enc, _) = ENCODINGS[job.to];
+
+    /* .torrent 文件单独处理:只重编码 name/path 等字段 */
+    if is_torrent_file(&job.input) {
+        return match transcode_torrent(&data, from_enc) {
+            Ok((out, changed)) => match std::fs::write(&job.output, out) {
+                Ok(_) if changed => (
+                    LogLevel::Warn,
+                    format!(
+                        "Done: {} (warning: info hash changed)",
+                        job.output.display()
+                    ),
+                ),
+                Ok(_) => (LogLevel::Info, format!("Done: {}", job.output.display())),
+                Err(e) => (
+                    LogLevel::Error,
+                    TranscodeError::from_io(&job.output, e).message(job.lang),
+                ),
+            },
+            Err(e) => (LogLevel::Error, e),
+        };
+    }
+
+    /* .reg 文件单独处理:自动识别变体并改写头部/BOM */
+    if is_reg_file(&job.input) {
+        let out = transcode_reg(&data, from_enc, to_enc);
+        return match std::fs::write(&job.output, out) {
+            Ok(_) => (LogLevel::Info, format!("Done: {}", job.output.display())),
+            Err(e) => (
+                LogLevel::Error,
+                TranscodeError::from_io(&job.output, e).message(job.lang),
+            ),
+        };
+    }
+
+    /* 像二进制就别硬转; Ask 在界面层已经拦过, 批量里等同跳过 */
+    if job.binary != BinaryPolicy::Force && binary_check_applies(job.from) && looks_binary(&data) {
+        return (
+            LogLevel::Warn,
+            format!("{}: {}", job.input.display(), t("skipped_binary", job.lang)),
+        );
+    }
+
+    /* 退路链命中就顶掉单选的来源编码, 旁记和报错跟着走 */
+    if let Some(pick) = chain_pick(&job.chain, &data, job.strip_bom) {
+        job.from = pick;
+    }
+    let (from_enc, _) = ENCODINGS[job.from];
+
+    let bytes = if job.strip_bom {
+        strip_bom_idx(&data, job.from)
+    } else {
+        &data
+    };
+
+    let decoded = decode_idx(job.from, bytes);
+    /* 有替换符才回头严格扫一遍定位偏移, 干净的文件不多花时间 */
+    let decode_err = if !is_manual_target(job.from) && decoded.contains('\u{FFFD}') {
+        first_decode_error(from_enc, bytes)
+    } else {
+        None
+    };
+    tx.send(WorkerMsg::Progress(tx.lane, name, 0.7)).ok();
+    let decoded = normalize_eol(&decoded, job.eol);
+
+    /* ASS/SSA: 样式行的 Encoding 字段跟着目标编码走 */
+    let decoded = if is_ass_file(&job.input) {
+        fix_ass_encoding(&decoded, to_enc)
+    } else {
+        decoded
+    };
+
+    /* HTML/XML: 编码声明跟着目标编码改写 */
+    let decoded = if is_html_file(&job.input) || is_xml_file(&job.input) {
+        rewrite_charset_decl(&decoded, to_enc)
+    } else {
+        decoded
+    };
+
+    /* .cue: 顺带修复引用的音频文件名 */
+    let cue_fixed = if is_cue_file(&job.input) {
+        let refs = cue_file_refs(&decoded);
+        job.input
+            .parent()
+            .map(|dir| repair_cue_refs(dir, &refs, from_enc))
+            .unwrap_or(0)
+    } else {
+        0
+    };
+
+    let (encoded, _) = encode_idx(job.to, &decoded);
+
+    /* encoding_rs 对编不出来的字符写 &#N; 引用, 数引用差得出个数 */
+    let unmappable = {
+        let refs_in = decoded.matches("&#").count();
+        let refs_out = encoded.windows(2).filter(|w| w == b"&#").count();
+        refs_out.saturating_sub(refs_in)
+    };
+
+    /* .csv/.tsv: 校验转码没弄坏表格结构 */
+    let csv_warning = if is_csv_file(&job.input) {
+        let round_trip = decode_idx(job.to, &encoded);
+        csv_structure_warning(&job.input, &decoded, &round_trip)
+    } else {
+        None
+    };
+
+    let mut out = Vec::with_capacity(encoded.len() + 4);
+    if job.write_bom
+        && let Some(bom) = bom_of_idx(job.to)
+    {
+        out.extend_from_slice(bom);
+    }
+    out.extend_from_slice(&encoded);
+
+    /* 播放列表的扩展名随目标编码修正: .m3u <-> .m3u8 */
+    let output = if is_m3u_file(&job.input) && is_m3u_file(&job.output) {
+        fixup_m3u_ext(&job.output, to_enc)
+    } else {
+        job.output.clone()
+    };
+
+    /* 字幕可选加语言后缀: a.srt -> a.zh.srt */
+    let output = if is_subtitle_file(&job.input) && !job.sub_suffix.is_empty() {
+        subtitle_suffixed(&output, &job.sub_suffix)
+    } else {
+        output
+    };
+
+    /* 原地转换: 可选先备份,写入失败时用备份恢复原文件 */
+    let in_place = job.input == output;
+    let bak = if in_place && job.backup {
+        let bak = bak_path(&job.input);
+        if let Err(e) = std::fs::copy(&job.input, &bak) {
+            return (
+                LogLevel::Error,
+                TranscodeError::from_io(&bak, e).message(job.lang),
+            );
+        }
+        Some(bak)
+    } else {
+        None
+    };
+
+    match std::fs::write(&output, out) {
+        Ok(_) => {
+            /* 原地转换改了扩展名时,旧文件已经没有用了 */
+            if job.input == job.output && output != job.input {
+                std::fs::remove_file(&job.input).ok();
+            }
+            if job.sidecar {
+                write_sidecar(&job, &output, &data);
+            }
+            let mut extras = Vec::new();
+            /* 回读校验: 输出再解一遍要和编码前的文本一致 */
+            if job.verify {
+                let round_trip = std::fs::read(&output)
+                    .map(|d| decode_idx(job.to, strip_bom_idx(&d, job.to)))
+                    .unwrap_or_default();
+                if round_trip != decoded {
+                    extras.push(t("verify_fail", job.lang).to_string());
+                }
+            }
+            if let Some(off) = decode_err {
+                extras.push(TranscodeError::Decode(job.input.clone(), off).message(job.lang));
+            }
+            if unmappable > 0 {
+                extras.push(
+                    TranscodeError::Unmappable(job.input.clone(), unmappable).message(job.lang),
+                );
+            }
+            if cue_fixed > 0 {
+                extras.push(format!("{} referenced files renamed", cue_fixed));
+            }
+            if let Some(warning) = csv_warning {
+                extras.push(warning);
+            }
+            if extras.is_empty() {
+                (LogLevel::Info, format!("Done: {}", output.display()))
+            } else {
+                (
+                    LogLevel::Warn,
+                    format!("Done: {} ({})", output.display(), extras.join("; ")),
+                )
+            }
+        }
+        Err(e) => {
+            if let Some(bak) = &bak {
+                std::fs::copy(bak, &job.input).ok();
+            }
+            (
+                LogLevel::Error,
+                TranscodeError::from_io(&output, e).message(job.lang),
+            )
+        }
+    }
+}
+
+/* ======================= 文本标签页 ======================= */
+/*
+    文本模式的多标签: 每页自己的输入输出和编码选择,
+    切页时把当前字段存回快照、换上目标页的,
+    其余代码照旧操作 self 上的字段
+*/
+struct TextDoc {
+    input: String,
+    output: String,
+    from: usize,
+    to: usize,
+}
+
+/* ======================= App ======================= */
+struct CodeTransApp {
+    lang: Language,
+    mode: Mode,
+    theme: ThemePreset,
+
+    from_idx: usize,
+    to_idx: usize,
+
+    input_text: String,
+    output_text: String,
+    text_report: String,
+    /* 文本模式实时转码的防抖: 最近一次修改时间 */
+    live_edit: Option<Instant>,
+    live_opts: TextOpts,
+    in_escaped: bool,
+    in_base64: bool,
+    as_html: bool,
+    out_repr: ByteRepr,
+
+    input_file: Option<PathBuf>,
+    output_file: Option<PathBuf>,
+
+    strip_bom: bool,
+    write_bom: bool,
+    eol: LineEnding,
+
+    conflict: ConflictPolicy,
+    pending_conflict: Option<(PathBuf, PathBuf)>,
+    /* 非原地模式下输出和输入是同一个文件时要单独确认 */
+    pending_same: Option<(PathBuf, PathBuf)>,
+    /* 疑似二进制待确认的任务 */
+    pending_binary: Option<(PathBuf, PathBuf)>,
+    binary_policy: BinaryPolicy,
+    /* 确认条上点了强转, 下一个任务放行一次 */
+    binary_force_once: bool,
+    sandbox: bool,
+    trusted_dirs: Vec<PathBuf>,
+    /* 本次运行内放行一次后不再拦截 */
+    sandbox_once: bool,
+    pending_sandbox: Option<(PathBuf, SandboxAction)>,
+    /* 深链接带 --autostart 时在第一帧触发任务 */
+    autostart: bool,
+    /* Ctrl+Enter 请求开始, 由当前模式的开始按钮逻辑消费 */
+    start_requested: bool,
+
+    presets: Vec<Preset>,
+    preset_name: String,
+    /* 输出命名规则: 自动建议的输出名是否带编码后缀 */
+    suffix_output: bool,
+    pending_convert: Option<PendingConvert>,
+    zip_password: String,
+    sub_suffix: String,
+    /* 仅查看: 解码后只在只读窗格里显示, 不写盘 */
+    view_only: bool,
+    viewer_text: Option<std::rc::Rc<String>>,
+    decode_cache: Option<DecodeCache>,
+
+    /* 文件对话框的起始目录,记住上次用过的位置 */
+    last_dir: Option<PathBuf>,
+
+    preview_bytes: Option<Vec<u8>>,
+
+    input_dir: Option<PathBuf>,
+    batch_roots: Vec<PathBuf>,
+    output_dir: Option<PathBuf>,
+    pattern: String,
+    name_tpl: String,
+    /* 视频目录配对: 只转与视频同名的字幕 */
+    pair_subs: bool,
+    in_place: bool,
+    backup: bool,
+    results: Vec<(String, String)>,
+    rename_plan: Vec<(PathBuf, PathBuf)>,
+    analyze_rows: Vec<AnalyzeRow>,
+    analyze_sort: (usize, bool),
+
+    repair_input: String,
+    repair_results: Vec<RepairCandidate>,
+
+    /* 对照解码: 十六进制输入 + 四个并排的解码编码 */
+    play_input: String,
+    play_encs: [usize; 4],
+
+    /* 外观: 明暗模式与字号缩放 */
+    color_mode: ColorMode,
+    font_scale: f32,
+
+    /* 转码时附带 .meta.json 旁记 */
+    sidecar: bool,
+    /* 写完回读核对 */
+    verify_after: bool,
+
+    /* 窗口宽度不足时改竖排 + 页签的紧凑布局 */
+    narrow: bool,
+    compare_tab: usize,
+    preview_tab: usize,
+
+    /* 字符表弹窗 */
+    show_charmap: bool,
+    charmap_query: String,
+
+    messages: Vec<StatusMsg>,
+    log: Vec<LogEntry>,
+    /* 只显示不低于该级别的日志 */
+    log_min: LogLevel,
+    /* 每条并行车道一格: 车道号 -> (文件名, 进度) */
+    progress: std::collections::BTreeMap<usize, (String, f32)>,
+    /* (开始时刻, 最近上报时刻, 已处理字节, 总字节) */
+    byte_progress: Option<(Instant, Instant, u64, u64)>,
+    history: Vec<HistoryEntry>,
+
+    rx: Option<mpsc::Receiver<WorkerMsg>>,
+    /* 最近一次批量任务的发送端, 答复完的冲突条目从这里接回去 */
+    worker_tx: Option<WorkerTx>,
+    /* Ask 策略拦下的批量冲突, 等用户逐个或一键答复 */
+    batch_conflicts: Vec<(FileJob, String)>,
+    conflict_apply_all: bool,
+    /* 给工作线程用来请求重画 */
+    egui_ctx: Option<egui::Context>,
+    /* 上一帧的编码对, 变了才套编码对规则 */
+    last_pair: (usize, usize),
+    /* 文本模式标签页快照, 当前页的份在切页时才更新 */
+    docs: Vec<TextDoc>,
+    doc_idx: usize,
+    /* 最近一次成功转换的输出, 给打开按钮用 */
+    last_output: Option<PathBuf>,
+    auto_open: bool,
+    /* 目录模式: 只转新文件和改动过的 */
+    incremental: bool,
+    url_input: String,
+    /* 来源编码退路链 (编码表下标, 按顺序试) */
+    fallback_chain: Vec<usize>,
+    fallback_add: usize,
+}
+
+impl Default for CodeTransApp {
+    fn default() -> Self {
+        Self {
+            lang: Language::Zh,
+            mode: Mode::Text,
+            theme: ThemePreset::Default,
+            from_idx: 0,
+            to_idx: 3, // UTF-8 -> GBK
+            input_text: String::new(),
+            output_text: String::new(),
+            text_report: String::new(),
+            live_edit: None,
+            live_opts: TextOpts {
+                from: 0,
+                to: 3,
+                eol: LineEnding::Keep,
+                in_escaped: false,
+                in_base64: false,
+                as_html: false,
+                repr: ByteRepr::Hex,
+            },
+            in_escaped: false,
+            in_base64: false,
+            as_html: false,
+            out_repr: ByteRepr::Hex,
+            input_file: None,
+            output_file: None,
+            strip_bom: true,
+            write_bom: false,
+            eol: LineEnding::Keep,
+            conflict: ConflictPolicy::Ask,
+            pending_conflict: None,
+            pending_same: None,
+            pending_binary: None,
+            binary_policy: BinaryPolicy::Ask,
+            binary_force_once: false,
+            sandbox: false,
+            trusted_dirs: Vec::new(),
+            sandbox_once: false,
+            pending_sandbox: None,
+            autostart: false,
+            start_requested: false,
+            presets: Vec::new(),
+            preset_name: String::new(),
+            suffix_output: true,
+            pending_convert: None,
+            zip_password: String::new(),
+            sub_suffix: String::new(),
+            view_only: false,
+            viewer_text: None,
+            decode_cache: None,
+            last_dir: None,
+            preview_bytes: None,
+            input_dir: None,
+            batch_roots: Vec::new(),
+            output_dir: None,
+            pattern: "*.txt".into(),
+            name_tpl: "{relpath}".into(),
+            pair_subs: false,
+            in_place: false,
+            backup: true,
+            results: Vec::new(),
+            rename_plan: Vec::new(),
+            analyze_rows: Vec::new(),
+            analyze_sort: (0, false),
+            repair_input: String::new(),
+            repair_results: Vec::new(),
+            play_input: String::new(),
+            play_encs: [0, 3, 5, 6], // UTF-8 / GBK / BIG5 / Shift_JIS
+            color_mode: ColorMode::System,
+            font_scale: 1.0,
+            sidecar: false,
+            verify_after: false,
+            narrow: false,
+            compare_tab: 0,
+            preview_tab: 0,
+            show_charmap: false,
+            charmap_query: String::new(),
+            messages: Vec::new(),
+            log: Vec::new(),
+            log_min: LogLevel::Info,
+            progress: std::collections::BTreeMap::new(),
+            byte_progress: None,
+            history: load_history(),
+            rx: None,
+            worker_tx: None,
+            batch_conflicts: Vec::new(),
+            conflict_apply_all: false,
+            egui_ctx: None,
+            last_pair: (usize::MAX, usize::MAX),
+            docs: Vec::new(),
+            doc_idx: 0,
+            last_output: None,
+            auto_open: false,
+            incremental: false,
+            url_input: String::new(),
+            fallback_chain: Vec::new(),
+            fallback_add: 0,
+        }
+    }
+}
+
+/* ======================= 设置持久化 ======================= */
+/*
+    通过 eframe 的 storage 记住语言、编码选择和
+    最近目录,窗口大小由 persistence 特性自己保存
+*/
+impl CodeTransApp {
+    fn from_storage(storage: Option<&dyn eframe::Storage>) -> Self {
+        let mut app = Self::default();
+        let Some(storage) = storage else {
+            app.lang = detect_os_language();
+            app.last_pair = (app.from_idx, app.to_idx);
+            return app;
+        };
+
+        /* 没存过语言才按系统区域设置猜 */
+        app.lang = match storage.get_string("lang") {
+            Some(v) => Language::from_tag(&v).unwrap_or(app.lang),
+            None => detect_os_language(),
+        };
+        if let Some(i) = storage.get_string("from").and_then(|v| v.parse().ok())
+            && i < ENCODINGS.len()
+        {
+            app.from_idx = i;
+        }
+        if let Some(i) = storage.get_string("to").and_then(|v| v.parse().ok())
+            && i < ENCODINGS.len()
+        {
+            app.to_idx = i;
+        }
+        if let Some(d) = storage.get_string("last_dir") {
+            app.last_dir = Some(PathBuf::from(d));
+        }
+        if let Some(d) = storage.get_string("output_dir") {
+            app.output_dir = Some(PathBuf::from(d));
+        }
+        if let Some(v) = storage.get_string("sandbox") {
+            app.sandbox = v == "1";
+        }
+        if let Some(v) = storage.get_string("auto_open") {
+            app.auto_open = v == "1";
+        }
+        if let Some(v) = storage.get_string("incremental") {
+            app.incremental = v == "1";
+        }
+        if let Some(v) = storage.get_string("fallback_chain") {
+            app.fallback_chain = v
+                .split(',')
+                .filter_map(|p| p.parse().ok())
+                .filter(|i| *i < ENCODINGS.len())
+                .collect();
+        }
+        if let Some(v) = storage.get_string("trusted_dirs") {
+            app.trusted_dirs = v.lines().map(PathBuf::from).collect();
+        }
+        if let Some(v) = storage.get_string("presets") {
+            app.presets = presets_from_string(&v);
+        }
+        if let Some(v) = storage.get_string("suffix_out") {
+            app.suffix_output = v == "1";
+        }
+        if let Some(m) = storage
+            .get_string("color_mode")
+            .as_deref()
+            .and_then(ColorMode::from_tag)
+        {
+            app.color_mode = m;
+        }
+        if let Some(s) = storage
+            .get_string("font_scale")
+            .and_then(|v| v.parse().ok())
+        {
+            app.font_scale = f32::clamp(s, 0.8, 2.0);
+        }
+        app.last_pair = (app.from_idx, app.to_idx);
+        app
+    }
+
+    fn apply_preset(&mut self, i: usize) {
+        let Some(p) = self.presets.get(i).cloned() else {
+            return;
+        };
+        self.from_idx = p.from;
+        self.to_idx = p.to;
+        self.strip_bom = p.strip_bom;
+        self.write_bom = p.write_bom;
+        self.eol = p.eol;
+        self.suffix_output = p.suffix;
+        self.push_msg(
+            MsgLevel::Info,
+            format!("{}: {}", t("preset", self.lang), p.name),
+        );
+    }
+
+    /* 命令行深链接参数覆盖存储里的设置 */
+    fn apply_launch(&mut self, launch: LaunchArgs) {
+        if let Some(mode) = launch.mode {
+            self.mode = mode;
+        }
+        if let Some(i) = launch.from {
+            self.from_idx = i;
+        }
+        if let Some(i) = launch.to {
+            self.to_idx = i;
+        }
+        if let Some(input) = launch.input {
+            if self.mode == Mode::Dir {
+                self.batch_roots.push(input);
+            } else {
+                self.preview_bytes = read_preview(&input);
+                self.input_file = Some(input);
+            }
+        }
+        if let Some(output) = launch.output {
+            if self.mode == Mode::Dir {
+                self.output_dir = Some(output);
+            } else {
+                self.output_file = Some(output);
+            }
+        }
+        self.autostart = launch.autostart;
+    }
+
+    fn file_dialog(&self) -> rfd::FileDialog {
+        let mut dlg = rfd::FileDialog::new();
+        if let Some(dir) = &self.last_dir {
+            dlg = dlg.set_directory(dir);
+        }
+        dlg
+    }
+}
+
+impl App for CodeTransApp {
+    fn save(&mut self, storage: &mut dyn eframe::Storage) {
+        storage.set_string("lang", self.lang.tag().into());
+        storage.set_string("from", self.from_idx.to_string());
+        storage.set_string("to", self.to_idx.to_string());
+        if let Some(d) = &self.last_dir {
+            storage.set_string("last_dir", d.display().to_string());
+        }
+        if let Some(d) = &self.output_dir {
+            storage.set_string("output_dir", d.display().to_string());
+        }
+        storage.set_string("sandbox", if self.sandbox { "1" } else { "0" }.into());
+        storage.set_string("auto_open", if self.auto_open { "1" } else { "0" }.into());
+        storage.set_string(
+            "incremental",
+            if self.incremental { "1" } else { "0" }.into(),
+        );
+        let dirs: Vec<String> = self
+            .trusted_dirs
+            .iter()
+            .map(|d| d.display().to_string())
+            .collect();
+        storage.set_string("trusted_dirs", dirs.join("\n"));
+        storage.set_string("presets", presets_to_string(&self.presets));
+        storage.set_string(
+            "suffix_out",
+            if self.suffix_output { "1" } else { "0" }.into(),
+        );
+        storage.set_string("color_mode", self.color_mode.tag().into());
+        storage.set_string("font_scale", format!("{:.1}", self.font_scale));
+    }
+
+    fn update(&mut self, ctx: &egui::Context, _: &mut eframe::Frame) {
+        self.narrow = ctx.content_rect().width() < NARROW_WIDTH;
+
+        /* 编码对换了就套对应的默认流水线, 之后用户改选项即是覆盖 */
+        if (self.from_idx, self.to_idx) != self.last_pair {
+            self.last_pair = (self.from_idx, self.to_idx);
+            if let Some(rule) = pair_rule_for(self.from_idx, self.to_idx) {
+                if let Some(v) = rule.strip_bom {
+                    self.strip_bom = v;
+                }
+                if let Some(v) = rule.write_bom {
+                    self.write_bom = v;
+                }
+                if let Some(v) = rule.eol {
+                    self.eol = v;
+                }
+                if let Some(v) = rule.backup {
+                    self.backup = v;
+                }
+                self.push_msg(MsgLevel::Info, t("pair_defaults", self.lang).into());
+            }
+        }
+
+        /* --autostart: 界面起来后直接开始预填好的任务 */
+        if self.autostart {
+            self.autostart = false;
+            match self.mode {
+                Mode::File => {
+                    if let Some(i) = self.input_file.clone() {
+                        let o = self.output_file.clone().unwrap_or_else(|| {
+                            self.in_place = true;
+                            i.clone()
+                        });
+                        self.start_file_job(i, o);
+                    }
+                }
+                Mode::Dir if !self.batch_roots.is_empty() => {
+                    if self.output_dir.is_none() {
+                        self.in_place = true;
+                    }
+                    self.start_batch_job();
+                }
+                _ => {}
+            }
+        }
+
+        /* Ctrl+1..9 套用对应序号的预设 */
+        const PRESET_KEYS: [egui::Key; 9] = [
+            egui::Key::Num1,
+            egui::Key::Num2,
+            egui::Key::Num3,
+            egui::Key::Num4,
+            egui::Key::Num5,
+            egui::Key::Num6,
+            egui::Key::Num7,
+            egui::Key::Num8,
+            egui::Key::Num9,
+        ];
+        let quick = ctx.input(|i| {
+            PRESET_KEYS
+                .iter()
+                .position(|k| i.modifiers.ctrl && i.key_pressed(*k))
+        });
+        if let Some(i) = quick {
+            self.apply_preset(i);
+        }
+
+        /* 快捷键: Ctrl+O 打开, Ctrl+Enter 开始, Ctrl+L 交换编码 */
+        let (open, start, swap) = ctx.input_mut(|i| {
+            (
+                i.consume_shortcut(&egui::KeyboardShortcut::new(
+                    egui::Modifiers::CTRL,
+                    egui::Key::O,
+                )),
+                i.consume_shortcut(&egui::KeyboardShortcut::new(
+                    egui::Modifiers::CTRL,
+                    egui::Key::Enter,
+                )),
+                i.consume_shortcut(&egui::KeyboardShortcut::new(
+                    egui::Modifiers::CTRL,
+                    egui::Key::L,
+                )),
+            )
+        });
+        if open {
+            self.open_input();
+        }
+        if start {
+            self.start_requested = true;
+        }
+        if swap {
+            self.swap_encodings();
+        }
+
+        egui::TopBottomPanel::top("menu").show(ctx, |ui| {
+            self.ui_menu(ui);
+        });
+
+        egui::CentralPanel::default().show(ctx, |ui| {
+            /* 整体套一层竖向滚动, 窄高窗口里内容放不下也能用 */
+            egui::ScrollArea::vertical()
+                .id_salt("main")
+                .auto_shrink([false; 2])
+                .show(ui, |ui| {
+                    ui.horizontal_wrapped(|ui| {
+                        egui::ComboBox::from_id_salt("lang")
+                            .selected_text(self.lang.native_name())
+                            .show_ui(ui, |ui| {
+                                for l in Language::ALL {
+                                    ui.selectable_value(&mut self.lang, l, l.native_name());
+                                }
+                            });
+
+                        ui.separator();
+                        ui.label(t("theme", self.lang));
+                        let before = (self.theme, self.color_mode, self.font_scale);
+                        for (preset, key) in [
+                            (ThemePreset::Default, "theme_default"),
+                            (ThemePreset::HighContrast, "theme_hc"),
+                            (ThemePreset::LargeText, "theme_large"),
+                        ] {
+                            ui.selectable_value(&mut self.theme, preset, t(key, self.lang));
+                        }
+                        ui.separator();
+                        for (mode, key) in [
+                            (ColorMode::System, "theme_system"),
+                            (ColorMode::Light, "theme_light"),
+                            (ColorMode::Dark, "theme_dark"),
+                        ] {
+                            ui.selectable_value(&mut self.color_mode, mode, t(key, self.lang));
+                        }
+                        ui.label(t("font_size", self.lang));
+                        ui.add(
+                            egui::Slider::new(&mut self.font_scale, 0.8..=2.0)
+                                .step_by(0.1)
+                                .show_value(false),
+                        );
+                        if (self.theme, self.color_mode, self.font_scale) != before {
+                            apply_theme(ui.ctx(), self.theme, self.color_mode, self.font_scale);
+                        }
+                    });
+
+                    ui.separator();
+
+                    ui.horizontal_wrapped(|ui| {
+                        ui.selectable_value(&mut self.mode, Mode::Text, t("text", self.lang));
+                        ui.selectable_value(&mut self.mode, Mode::File, t("file", self.lang));
+                        ui.selectable_value(&mut self.mode, Mode::Dir, t("dir", self.lang));
+                        ui.selectable_value(&mut self.mode, Mode::Names, t("names", self.lang));
+                        ui.selectable_value(&mut self.mode, Mode::Repair, t("repair", self.lang));
+                        ui.selectable_value(
+                            &mut self.mode,
+                            Mode::Playground,
+                            t("playground", self.lang),
+                        );
+                    });
+
+                    ui.separator();
+
+                    /* 预设下拉: 点名字套用, ✕ 删除; 右边保存当前设置 */
+                    ui.horizontal(|ui| {
+                        ui.label(t("preset", self.lang));
+                        let mut apply = None;
+                        let mut delete = None;
+                        egui::ComboBox::from_id_salt("preset")
+                            .selected_text(t("preset_pick", self.lang))
+                            .show_ui(ui, |ui| {
+                                for (i, p) in self.presets.iter().enumerate() {
+                                    ui.horizontal(|ui| {
+                                        if ui.button(&p.name).clicked() {
+                                            apply = Some(i);
+                                        }
+                                        ui.push_id(("preset", i), |ui| {
+                                            if ui.small_button("✕").clicked() {
+                                                delete = Some(i);
+                                            }
+                                        });
+                                    });
+                                }
+                            });
+                        ui.add(
+                            egui::TextEdit::singleline(&mut self.preset_name)
+                                .hint_text(t("preset_name", self.lang))
+                                .desired_width(120.0),
+                        );
+                        if ui.button(t("save_preset", self.lang)).clicked() {
+                            let name = self.preset_name.trim().to_string();
+                            if !name.is_empty() {
+                                let preset = Preset {
+                                    name: name.clone(),
+                                    from: self.from_idx,
+                                    to: self.to_idx,
+                                    strip_bom: self.strip_bom,
+                                    write_bom: self.write_bom,
+                                    eol: self.eol,
+                                    suffix: self.suffix_output,
+                                };
+                                /* 同名覆盖 */
+                                match self.presets.iter_mut().find(|p| p.name == name) {
+                                    Some(slot) => *slot = preset,
+                                    None => self.presets.push(preset),
+                                }
+                                self.preset_name.clear();
+                            }
+                        }
+                        if let Some(i) = delete {
+                            self.presets.remove(i);
+                        }
+                        if let Some(i) = apply {
+                            self.apply_preset(i);
+                        }
+                    });
+
+                    ui.horizontal_wrapped(|ui| {
+                        ui.label(t("from", self.lang));
+                        encoding_combo(ui, "from", &mut self.from_idx);
+                        if ui.button("⇄").on_hover_text(t("swap", self.lang)).clicked() {
+                            self.swap_encodings();
+                        }
+                        ui.label(t("to", self.lang));
+                        encoding_combo(ui, "to", &mut self.to_idx);
+                        ui.label(t("eol", self.lang));
+                        eol_combo(ui, self.lang, &mut self.eol);
+                    });
+
+                    ui.separator();
+
+                    match self.mode {
+                        Mode::Text => self.ui_text(ui),
+                        Mode::File => self.ui_file(ui),
+                        Mode::Dir => self.ui_dir(ui),
+                        Mode::Names => self.ui_names(ui),
+                        Mode::Repair => self.ui_repair(ui),
+                        Mode::Playground => self.ui_playground(ui),
+                    }
+
+                    /* rx 借着 self, 消息先攒下来, 出了借用再进状态列表 */
+                    let mut done_msgs: Vec<(LogLevel, String)> = Vec::new();
+                    if let Some(rx) = &self.rx {
+                        while let Ok(msg) = rx.try_recv() {
+                            match msg {
+                                WorkerMsg::Progress(lane, name, p) => {
+                                    self.progress.insert(lane, (name, p));
+                                }
+                                WorkerMsg::TotalBytes(total, ts) => {
+                                    self.byte_progress = Some((ts, ts, 0, total));
+                                }
+                                WorkerMsg::Bytes(n, ts) => {
+                                    if let Some((_, last, done, _)) = &mut self.byte_progress {
+                                        *last = ts;
+                                        *done += n;
+                                    }
+                                }
+                                WorkerMsg::FileResult(level, path, status) => {
+                                    self.log.push(LogEntry {
+                                        secs: now_secs(),
+                                        level,
+                                        text: format!("{}: {}", path, status),
+                                    });
+                                    self.results.push((path, status));
+                                }
+                                WorkerMsg::Conflict(job, label) => {
+                                    self.batch_conflicts.push((*job, label));
+                                }
+                                WorkerMsg::Analyze(row) => self.analyze_rows.push(row),
+                                WorkerMsg::History(level, entry) => {
+                                    /* 成功才记输出路径, 失败没有可打开的东西 */
+                                    if level == LogLevel::Info {
+                                        if self.auto_open {
+                                            open_in_folder(&entry.output);
+                                        }
+                                        self.last_output = Some(entry.output.clone());
+                                    }
+                                    append_history(&entry);
+                                    self.history.insert(0, entry);
+                                    self.history.truncate(HISTORY_LIMIT);
+                                }
+                                WorkerMsg::Done(level, s) => {
+                                    self.log.push(LogEntry {
+                                        secs: now_secs(),
+                                        level,
+                                        text: s.clone(),
+                                    });
+                                    done_msgs.push((level, s));
+                                    self.progress.clear();
+                                    self.byte_progress = None;
+                                }
+                            }
+                        }
+                    }
+
+                    for (level, s) in done_msgs {
+                        self.push_msg(level.as_msg(), s);
+                    }
+
+                    self.ui_log(ui);
+                });
+        });
+
+        self.ui_charmap(ctx);
+
+        /* 当前模式没有开始按钮时丢弃请求, 免得切模式误触发 */
+        self.start_requested = false;
+    }
+}
+
+/* ======================= 字符表 ======================= */
+/*
+    拼测试串时总有些敲不出来的字符: 零宽空格、BOM、
+    全角标点、方向控制符…… 列一张带名字的表,
+    按名字或码点搜出来点一下插进输入框
+*/
+static CHAR_MAP: &[(&str, char)] = &[
+    ("no-break space", '\u{00A0}'),
+    ("narrow no-break space", '\u{202F}'),
+    ("zero width space", '\u{200B}'),
+    ("zero width non-joiner", '\u{200C}'),
+    ("zero width joiner", '\u{200D}'),
+    ("word joiner", '\u{2060}'),
+    ("byte order mark", '\u{FEFF}'),
+    ("soft hyphen", '\u{00AD}'),
+    ("ideographic space", '\u{3000}'),
+    ("em space", '\u{2003}'),
+    ("en space", '\u{2002}'),
+    ("thin space", '\u{2009}'),
+    ("left-to-right mark", '\u{200E}'),
+    ("right-to-left mark", '\u{200F}'),
+    ("line separator", '\u{2028}'),
+    ("paragraph separator", '\u{2029}'),
+    ("next line", '\u{0085}'),
+    ("replacement character", '\u{FFFD}'),
+    ("object replacement", '\u{FFFC}'),
+    ("em dash", '\u{2014}'),
+    ("en dash", '\u{2013}'),
+    ("horizontal ellipsis", '\u{2026}'),
+    ("bullet", '\u{2022}'),
+    ("middle dot", '\u{00B7}'),
+    ("left single quote", '\u{2018}'),
+    ("right single quote", '\u{2019}'),
+    ("left double quote", '\u{201C}'),
+    ("right double quote", '\u{201D}'),
+    ("left angle quote", '\u{00AB}'),
+    ("right angle quote", '\u{00BB}'),
+    ("left corner bracket", '\u{300C}'),
+    ("right corner bracket", '\u{300D}'),
+    ("left white corner bracket", '\u{300E}'),
+    ("right white corner bracket", '\u{300F}'),
+    ("fullwidth comma", '\u{FF0C}'),
+    ("ideographic full stop", '\u{3002}'),
+    ("fullwidth question mark", '\u{FF1F}'),
+    ("fullwidth exclamation mark", '\u{FF01}'),
+    ("fullwidth colon", '\u{FF1A}'),
+    ("fullwidth semicolon", '\u{FF1B}'),
+    ("degree sign", '\u{00B0}'),
+    ("plus-minus sign", '\u{00B1}'),
+    ("multiplication sign", '\u{00D7}'),
+    ("division sign", '\u{00F7}'),
+    ("micro sign", '\u{00B5}'),
+    ("euro sign", '\u{20AC}'),
+    ("yen sign", '\u{00A5}'),
+    ("pound sign", '\u{00A3}'),
+    ("copyright sign", '\u{00A9}'),
+    ("registered sign", '\u{00AE}'),
+    ("trade mark sign", '\u{2122}'),
+    ("section sign", '\u{00A7}'),
+    ("check mark", '\u{2713}'),
+    ("ballot x", '\u{2717}'),
+    ("leftwards arrow", '\u{2190}'),
+    ("rightwards arrow", '\u{2192}'),
+];
+
+/* "U+4E2D" / "4e2d" 形式的码点输入 */
+fn charmap_lookup(query: &str) -> Option<char> {
+    let hex = query
+        .trim()
+        .trim_start_matches("U+")
+        .trim_start_matches("u+");
+    if hex.is_empty() || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    char::from_u32(u32::from_str_radix(hex, 16).ok()?)
+}
+
+/* 零宽和空白字符画不出来, 按钮上改显示码点 */
+fn char_visible(c: char) -> bool {
+    !c.is_whitespace()
+        && !matches!(
+            c,
+            '\u{00AD}' | '\u{200B}'
+                ..='\u{200F}' | '\u{2028}' | '\u{2029}' | '\u{2060}' | '\u{FEFF}'
+        )
+}
+
+/* ======================= UI ======================= */
+impl CodeTransApp {
+    fn text_opts(&self) -> TextOpts {
+        TextOpts {
+            from: self.from_idx,
+            to: self.to_idx,
+            eol: self.eol,
+            in_escaped: self.in_escaped,
+            in_base64: self.in_base64,
+            as_html: self.as_html,
+            repr: self.out_repr,
+        }
+    }
+
+    fn ui_text(&mut self, ui: &mut egui::Ui) {
+        if self.docs.is_empty() {
+            self.docs.push(TextDoc {
+                input: String::new(),
+                output: String::new(),
+                from: self.from_idx,
+                to: self.to_idx,
+            });
+        }
+
+        /* 标签条: 点切页, ＋开新页, ✖关当前页 */
+        let mut switch = None;
+        ui.horizontal(|ui| {
+            for i in 0..self.docs.len() {
+                if ui
+                    .selectable_label(i == self.doc_idx, format!("{}", i + 1))
+                    .clicked()
+                {
+                    switch = Some(i);
+                }
+            }
+            if ui.small_button("＋").clicked() {
+                self.stash_doc();
+                self.docs.push(TextDoc {
+                    input: String::new(),
+                    output: String::new(),
+                    from: self.from_idx,
+                    to: self.to_idx,
+                });
+                switch = Some(self.docs.len() - 1);
+            }
+            if self.docs.len() > 1 && ui.small_button("✖").clicked() {
+                self.docs.remove(self.doc_idx);
+                let next = self.doc_idx.min(self.docs.len() - 1);
+                /* 被关的页没了, 直接装入邻页 */
+                let d = &self.docs[next];
+                self.input_text = d.input.clone();
+                self.output_text = d.output.clone();
+                self.from_idx = d.from;
+                self.to_idx = d.to;
+                self.doc_idx = next;
+                self.live_edit = Some(Instant::now());
+            }
+        });
+        if let Some(i) = switch {
+            self.switch_doc(i);
+        }
+
+        ui.label(t("input", self.lang));
+        let edit = egui::TextEdit::multiline(&mut self.input_text).show(ui);
+        let response = edit.response.clone();
+
+        /* 字符检查器: 光标前或选区首个字符的码点 / 名称 /
+        UTF-8 字节 / 目标编码字节, 排查某个字符为何总出问题 */
+        if let Some(range) = edit.state.cursor.char_range() {
+            let (a, b) = (
+                range.primary.index.min(range.secondary.index),
+                range.primary.index.max(range.secondary.index),
+            );
+            let picked = if a != b {
+                self.input_text.chars().nth(a)
+            } else if a > 0 {
+                self.input_text.chars().nth(a - 1)
+            } else {
+                None
+            };
+            if let Some(c) = picked {
+                let hex = |bytes: &[u8]| {
+                    bytes
+                        .iter()
+                        .map(|b| format!("{:02X}", b))
+                        .collect::<Vec<_>>()
+                        .join(" ")
+                };
+                ui.horizontal_wrapped(|ui| {
+                    ui.monospace(format!("U+{:04X}", c as u32));
+                    if let Some((name, _)) = CHAR_MAP.iter().find(|(_, ch)| *ch == c) {
+                        ui.weak(*name);
+                    }
+                    let mut buf = [0u8; 4];
+                    ui.monospace(format!(
+                        "UTF-8: {}",
+                        hex(c.encode_utf8(&mut buf).as_bytes())
+                    ));
+                    let one = c.to_string();
+                    let label = ENCODINGS[self.to_idx].1;
+                    let (bytes, lossy) = encode_idx(self.to_idx, &one);
+                    if lossy {
+                        ui.colored_label(
+                            egui::Color32::LIGHT_RED,
+                            format!("{}: {}", label, t("unmappable", self.lang)),
+                        );
+                    } else {
+                        ui.monospace(format!("{}: {}", label, hex(&bytes)));
+                    }
+                });
+            }
+        }
+
+        /* 粘贴内容的形态提示: 点一下就启用对应的解码 */
+        if !self.in_escaped && !self.in_base64 && !self.input_text.trim().is_empty() {
+            let suggestion = if looks_like_hex(&self.input_text) {
+                Some(("looks_hex", false))
+            } else if looks_like_percent(&self.input_text) {
+                Some(("looks_pct", false))
+            } else if looks_like_base64(&self.input_text) {
+                Some(("looks_b64", true))
+            } else {
+                None
+            };
+            if let Some((key, b64)) = suggestion
+                && ui.small_button(t(key, self.lang)).clicked()
+            {
+                if b64 {
+                    self.in_base64 = true;
+                } else {
+                    self.in_escaped = true;
+                }
+            }
+        }
+
+        ui.horizontal(|ui| {
+            ui.checkbox(&mut self.in_escaped, t("in_escaped", self.lang));
+            ui.checkbox(&mut self.in_base64, t("in_base64", self.lang));
+            ui.checkbox(&mut self.as_html, t("as_html", self.lang));
+            ui.label(t("out_repr", self.lang));
+            ui.selectable_value(
+                &mut self.out_repr,
+                ByteRepr::Text,
+                t("repr_text", self.lang),
+            );
+            ui.selectable_value(&mut self.out_repr, ByteRepr::Hex, "\\x");
+            ui.selectable_value(&mut self.out_repr, ByteRepr::Percent, "%");
+            ui.selectable_value(&mut self.out_repr, ByteRepr::Base64, "Base64");
+            ui.selectable_value(&mut self.out_repr, ByteRepr::Entities, "&#N;");
+        });
+
+        ui.horizontal(|ui| {
+            if ui.button(t("paste_clip", self.lang)).clicked() {
+                if let Some(text) = clipboard_text() {
+                    self.input_text = text;
+                    self.live_edit = Some(Instant::now());
+                } else {
+                    self.push_msg(MsgLevel::Warn, t("clip_empty", self.lang).into());
+                }
+            }
+            if ui.button(t("copy_out", self.lang)).clicked() {
+                ui.ctx().copy_text(self.output_text.clone());
+            }
+            /* 读剪贴板 -> 转码 -> 写回,不经过输入框也能看到结果 */
+            if ui.button(t("convert_clip", self.lang)).clicked() {
+                if let Some(text) = clipboard_text() {
+                    self.input_text = text;
+                    let (output, report) = transcode_text(&self.input_text, self.text_opts());
+                    self.output_text = output;
+                    self.text_report = report.summary(self.as_html, self.eol, self.lang);
+                    set_clipboard_text(&self.output_text);
+                    self.live_edit = None;
+                    self.push_msg(MsgLevel::Info, t("clip_done", self.lang).into());
+                } else {
+                    self.push_msg(MsgLevel::Warn, t("clip_empty", self.lang).into());
+                }
+            }
+        });
+
+        /* 输入或选项一变就标记,防抖后自动转码,无需按钮 */
+        let opts = self.text_opts();
+        if response.changed() || opts != self.live_opts {
+            self.live_opts = opts;
+            self.live_edit = Some(Instant::now());
+        }
+
+        const DEBOUNCE: Duration = Duration::from_millis(300);
+        if let Some(edit) = self.live_edit {
+            let elapsed = edit.elapsed();
+            if elapsed >= DEBOUNCE {
+                let (output, report) = transcode_text(&self.input_text, self.text_opts());
+                self.output_text = output;
+                self.text_report = report.summary(self.as_html, self.eol, self.lang);
+                self.live_edit = None;
+            } else {
+                ui.ctx().request_repaint_after(DEBOUNCE - elapsed);
+            }
+        }
+
+        ui.separator();
+        ui.label(t("output", self.lang));
+        ui.text_edit_multiline(&mut self.output_text);
+        if !self.text_report.is_empty() {
+            ui.weak(&self.text_report);
+        }
+        self.ui_messages(ui);
+    }
+
+    /* 过了信任目录闸门才真正动文件 */
+    fn apply_rename(&mut self) {
+        let Some(root) = self.input_dir.clone() else {
+            return;
+        };
+        let (done, _) = apply_rename_plan(&root, &self.rename_plan);
+        self.push_msg(
+            MsgLevel::Info,
+            format!("Renamed: {} / {}", done, self.rename_plan.len()),
+        );
+        self.rename_plan.clear();
+    }
+
+    fn undo_rename(&mut self) {
+        let Some(root) = self.input_dir.clone() else {
+            return;
+        };
+        match undo_renames(&root) {
+            Ok(done) => self.push_msg(MsgLevel::Info, format!("Restored: {}", done)),
+            Err(e) => self.push_msg(MsgLevel::Error, e),
+        }
+    }
+
+    fn ui_names(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            if ui.button(t("select_dir", self.lang)).clicked() {
+                self.input_dir = self.file_dialog().pick_folder();
+                self.rename_plan.clear();
+                if let Some(d) = &self.input_dir {
+                    self.last_dir = Some(d.clone());
+                }
+            }
+            if let Some(d) = &self.input_dir {
+                ui.label(d.display().to_string());
+            }
+        });
+
+        let Some(root) = self.input_dir.clone() else {
+            return;
+        };
+
+        ui.horizontal(|ui| {
+            if ui.button(t("preview_names", self.lang)).clicked() {
+                let (from_enc, _) = ENCODINGS[self.from_idx];
+                self.rename_plan = collect_rename_plan(&root, from_enc);
+                if self.rename_plan.is_empty() {
+                    self.push_msg(MsgLevel::Info, t("no_renames", self.lang).into());
+                }
+            }
+            /* 改名和撤销跟转码一样过信任目录闸门, 拦下来走同一套提示 */
+            if !self.rename_plan.is_empty() && ui.button(t("apply_names", self.lang)).clicked() {
+                if let Some(dir) = self.sandbox_violation(&root) {
+                    self.pending_sandbox = Some((dir, SandboxAction::Rename));
+                } else {
+                    self.apply_rename();
+                }
+            }
+            if root.join(RENAME_UNDO).exists() && ui.button(t("undo_names", self.lang)).clicked() {
+                if let Some(dir) = self.sandbox_violation(&root) {
+                    self.pending_sandbox = Some((dir, SandboxAction::UndoRename));
+                } else {
+                    self.undo_rename();
+                }
+            }
+        });
+
+        self.ui_sandbox_prompt(ui);
+
+        if !self.rename_plan.is_empty() {
+            ui.separator();
+            egui::ScrollArea::vertical()
+                .id_salt("rename_plan")
+                .max_height(240.0)
+                .show(ui, |ui| {
+                    egui::Grid::new("rename_grid").striped(true).show(ui, |ui| {
+                        for (old, new) in &self.rename_plan {
+                            ui.label(old.display().to_string());
+                            ui.label("->");
+                            ui.label(new.display().to_string());
+                            ui.end_row();
+                        }
+                    });
+                });
+        }
+
+        ui.separator();
+        self.ui_messages(ui);
+    }
+
+    /* 左边敲十六进制字节, 右边同时看四种编码的解码结果 */
+    fn ui_playground(&mut self, ui: &mut egui::Ui) {
+        ui.label(t("play_input", self.lang));
+        ui.text_edit_multiline(&mut self.play_input);
+
+        let bytes = parse_escaped_bytes(&self.play_input);
+        ui.weak(format!(
+            "{} {}",
+            fmt_count(bytes.len(), self.lang),
+            t("bytes", self.lang)
+        ));
+
+        ui.separator();
+        for (i, enc_idx) in self.play_encs.iter_mut().enumerate() {
+            ui.horizontal(|ui| {
+                encoding_combo(ui, &format!("play_{}", i), enc_idx);
+                let decoded = decode_idx(*enc_idx, &bytes);
+                if decoded.contains('\u{FFFD}') {
+                    ui.colored_label(egui::Color32::LIGHT_RED, &decoded);
+                } else {
+                    ui.monospace(&decoded);
+                }
+            });
+        }
+    }
+
+    fn ui_repair(&mut self, ui: &mut egui::Ui) {
+        ui.label(t("garbled", self.lang));
+        ui.text_edit_multiline(&mut self.repair_input);
+
+        ui.horizontal(|ui| {
+            if ui.button(t("paste_clip", self.lang)).clicked()
+                && let Some(text) = clipboard_text()
+            {
+                self.repair_input = text;
+            }
+            if ui.button(t("analyze", self.lang)).clicked() {
+                self.repair_results = repair_candidates(&self.repair_input);
+                if self.repair_results.is_empty() {
+                    self.push_msg(MsgLevel::Info, t("no_candidates", self.lang).into());
+                }
+            }
+        });
+
+        if self.repair_results.is_empty() {
+            self.ui_messages(ui);
+            return;
+        }
+
+        ui.separator();
+        ui.label(t("candidates", self.lang));
+        egui::ScrollArea::vertical()
+            .id_salt("repair_candidates")
+            .show(ui, |ui| {
+                for (i, cand) in self.repair_results.iter().enumerate() {
+                    ui.push_id(i, |ui| {
+                        ui.horizontal(|ui| {
+                            ui.monospace(&cand.chain);
+                            ui.weak(format!("({})", cand.score));
+                            if ui.button(t("use_candidate", self.lang)).clicked() {
+                                ui.ctx().copy_text(cand.text.clone());
+                            }
+                        });
+                        ui.label(&cand.text);
+                        ui.separator();
+                    });
+                }
+            });
+    }
+
+    fn ui_file(&mut self, ui: &mut egui::Ui) {
+        if ui.button(t("select_input", self.lang)).clicked() {
+            self.input_file = self.file_dialog().pick_file();
+            self.preview_bytes = self.input_file.as_deref().and_then(read_preview);
+            if let Some(p) = &self.input_file {
+                self.last_dir = p.parent().map(Path::to_path_buf);
+                /* 选完输入就给一个同目录带编码后缀的建议输出,
+                只在想改的时候才需要打开保存对话框 */
+                if !self.in_place && self.suffix_output {
+                    self.output_file = Some(suggested_output(p, self.to_idx));
+                }
+            }
+        }
+        /* 从 URL 抓远程文本: 下到临时文件, 再走普通文件流程;
+        头里声明的 charset 预填来源编码, 用户照样能改 */
+        ui.horizontal(|ui| {
+            ui.label(t("from_url", self.lang));
+            ui.add(
+                egui::TextEdit::singleline(&mut self.url_input)
+                    .hint_text("http://")
+                    .desired_width(260.0),
+            );
+            if ui.button(t("fetch", self.lang)).clicked() && !self.url_input.trim().is_empty() {
+                match http_get(&self.url_input) {
+                    Ok((data, charset)) => {
+                        let path = std::env::temp_dir().join(url_file_name(&self.url_input));
+                        match std::fs::write(&path, &data) {
+                            Ok(_) => {
+                                if let Some(i) = charset.as_deref().and_then(encoding_index) {
+                                    self.from_idx = i;
+                                }
+                                self.preview_bytes = read_preview(&path);
+                                if !self.in_place && self.suffix_output {
+                                    self.output_file = Some(suggested_output(&path, self.to_idx));
+                                }
+                                self.input_file = Some(path);
+                                self.push_msg(
+                                    MsgLevel::Info,
+                                    format!(
+                                        "{} {} {}",
+                                        t("fetched", self.lang),
+                                        fmt_count(data.len(), self.lang),
+                                        t("bytes", self.lang)
+                                    ),
+                                );
+                            }
+                            Err(e) => self.push_msg(MsgLevel::Error, e.to_string()),
+                        }
+                    }
+                    Err(e) => self.push_msg(MsgLevel::Error, e),
+                }
+            }
+        });
+
+        if let Some(p) = &self.input_file {
+            ui.horizontal(|ui| {
+                ui.label(p.display().to_string());
+                if let Ok(meta) = std::fs::metadata(p) {
+                    ui.weak(fmt_size(meta.len()));
+                }
+            });
+        }
+        if !self.in_place {
+            ui.horizontal(|ui| {
+                if ui.button(t("select_output", self.lang)).clicked()
+                    && let Some(p) = self.file_dialog().save_file()
+                {
+                    self.output_file = Some(p);
+                }
+                if let Some(p) = &self.output_file {
+                    ui.label(p.display().to_string());
+                }
+            });
+        }
+
+        ui.horizontal(|ui| {
+            ui.checkbox(&mut self.strip_bom, t("strip_bom", self.lang));
+            ui.checkbox(&mut self.write_bom, t("write_bom", self.lang));
+            ui.checkbox(&mut self.in_place, t("in_place", self.lang));
+            if self.in_place {
+                ui.checkbox(&mut self.backup, t("backup", self.lang));
+            }
+            ui.checkbox(&mut self.view_only, t("view_only", self.lang));
+            ui.checkbox(&mut self.suffix_output, t("suffix_out", self.lang));
+            ui.checkbox(&mut self.sidecar, t("sidecar", self.lang));
+            ui.checkbox(&mut self.verify_after, t("verify", self.lang));
+            ui.checkbox(&mut self.auto_open, t("auto_open", self.lang));
+            ui.separator();
+            ui.label(t("binary", self.lang));
+            for (policy, key) in [
+                (BinaryPolicy::Skip, "skip"),
+                (BinaryPolicy::Ask, "ask"),
+                (BinaryPolicy::Force, "force"),
+            ] {
+                ui.selectable_value(&mut self.binary_policy, policy, t(key, self.lang));
+            }
+        });
+
+        self.ui_fallback(ui);
+
+        /* 加密 ZIP 的密码输入 */
+        if self.input_file.as_deref().is_some_and(is_zip_file) {
+            ui.horizontal(|ui| {
+                ui.label(t("zip_pwd", self.lang));
+                ui.add(egui::TextEdit::singleline(&mut self.zip_password).password(true));
+            });
+        }
+
+        /* 字幕的语言后缀 */
+        if self.input_file.as_deref().is_some_and(is_subtitle_file) {
+            ui.horizontal(|ui| {
+                ui.label(t("sub_suffix", self.lang));
+                ui.text_edit_singleline(&mut self.sub_suffix);
+            });
+        }
+
+        self.ui_sandbox_settings(ui);
+
+        ui.horizontal(|ui| {
+            ui.label(t("conflict", self.lang));
+            for (policy, key) in [
+                (ConflictPolicy::Ask, "ask"),
+                (ConflictPolicy::Overwrite, "overwrite"),
+                (ConflictPolicy::Skip, "skip"),
+                (ConflictPolicy::Rename, "rename"),
+            ] {
+                ui.selectable_value(&mut self.conflict, policy, t(key, self.lang));
+            }
+        });
+
+        if (ui.button(t("start", self.lang)).clicked() || std::mem::take(&mut self.start_requested))
+            && let Some(i) = self.input_file.clone()
+            && let Some(o) = if self.in_place || self.view_only {
+                Some(i.clone())
+            } else {
+                self.output_file.clone()
+            }
+        {
+            if self.view_only {
+                /* 只读查看: 解码整个文件, 什么都不写 */
+                self.viewer_text = match self.ensure_decoded(&i) {
+                    Ok(_) => self
+                        .decode_cache
+                        .as_ref()
+                        .map(|c| std::rc::Rc::clone(&c.text)),
+                    Err(e) => {
+                        self.push_msg(MsgLevel::Error, e);
+                        None
+                    }
+                };
+            } else if self.in_place {
+                /* 原地转换必然同名,由备份机制兜底,但同样先过预览 */
+                self.prepare_convert(i, o);
+            } else if same_file(&i, &o) {
+                /* 没开原地却选了输入文件本身: 单独确认, 预先勾上备份,
+                避免 read-write 流程在半路失败时砸掉源文件 */
+                self.backup = true;
+                self.pending_same = Some((i, o));
+            } else if o.exists() {
+                match self.conflict {
+                    ConflictPolicy::Ask => self.pending_conflict = Some((i, o)),
+                    ConflictPolicy::Overwrite => self.prepare_convert(i, o),
+                    ConflictPolicy::Skip => {
+                        self.push_msg(MsgLevel::Warn, t("skipped", self.lang).into())
+                    }
+                    ConflictPolicy::Rename => {
+                        let renamed = renamed_path(&o);
+                        self.prepare_convert(i, renamed);
+                    }
+                }
+            } else {
+                self.prepare_convert(i, o);
+            }
+        }
+
+        self.ui_sandbox_prompt(ui);
+
+        /* 只读查看窗格 */
+        if self.viewer_text.is_some() {
+            ui.separator();
+            ui.horizontal(|ui| {
+                ui.label(t("viewer", self.lang));
+                if ui.small_button("✕").clicked() {
+                    self.viewer_text = None;
+                }
+            });
+        }
+        if let Some(text) = &mut self.viewer_text {
+            egui::ScrollArea::vertical()
+                .id_salt("viewer")
+                .max_height(300.0)
+                .show(ui, |ui| {
+                    ui.add(
+                        egui::TextEdit::multiline(&mut text.as_str()).desired_width(f32::INFINITY),
+                    );
+                });
+        }
+
+        /* 写盘前的前后对比,确认才是第二步 */
+        if let Some(pending) = &self.pending_convert {
+            ui.separator();
+            ui.label(t("compare", self.lang));
+            if pending.lost_total > 0 {
+                ui.colored_label(
+                    egui::Color32::LIGHT_RED,
+                    format!("{} {}", pending.lost_total, t("lost_chars", self.lang)),
+                );
+                let samples: String = pending.lost_samples.iter().collect();
+                ui.colored_label(
+                    egui::Color32::LIGHT_RED,
+                    format!("{}: {}", t("lost_samples", self.lang), samples),
+                );
+            }
+            if self.narrow {
+                /* 窄窗口放不下两栏, 前后改成页签切换 */
+                ui.horizontal(|ui| {
+                    ui.selectable_value(&mut self.compare_tab, 0, t("tab_before", self.lang));
+                    ui.selectable_value(&mut self.compare_tab, 1, t("tab_after", self.lang));
+                });
+                let text = if self.compare_tab == 0 {
+                    &pending.before
+                } else {
+                    &pending.after
+                };
+                egui::ScrollArea::vertical()
+                    .id_salt("compare_one")
+                    .max_height(200.0)
+                    .show(ui, |ui| {
+                        ui.monospace(text);
+                    });
+            } else {
+                ui.columns(2, |cols| {
+                    egui::ScrollArea::vertical()
+                        .id_salt("compare_before")
+                        .max_height(200.0)
+                        .show(&mut cols[0], |ui| {
+                            ui.monospace(&pending.before);
+                        });
+                    egui::ScrollArea::vertical()
+                        .id_salt("compare_after")
+                        .max_height(200.0)
+                        .show(&mut cols[1], |ui| {
+                            ui.monospace(&pending.after);
+                        });
+                });
+            }
+            ui.horizontal(|ui| {
+                if ui.button(t("commit", self.lang)).clicked()
+                    && let Some(pending) = self.pending_convert.take()
+                {
+                    self.start_file_job(pending.input, pending.output);
+                }
+                if ui.button(t("cancel", self.lang)).clicked() {
+                    self.pending_convert = None;
+                }
+            });
+        }
+
+        /* 原始字节和按 from 编码的解码结果并排预览 */
+        if let Some(bytes) = &self.preview_bytes {
+            let decoded = decode_idx(self.from_idx, bytes);
+            let narrow = self.narrow;
+            let mut preview_tab = self.preview_tab;
+            egui::CollapsingHeader::new(t("preview", self.lang)).show(ui, |ui| {
+                if narrow {
+                    /* 窄窗口: 字节和解码两栏收进页签 */
+                    ui.horizontal(|ui| {
+                        ui.selectable_value(&mut preview_tab, 0, t("bytes", self.lang));
+                        ui.selectable_value(&mut preview_tab, 1, t("repr_text", self.lang));
+                    });
+                    egui::ScrollArea::vertical()
+                        .id_salt("preview_one")
+                        .max_height(160.0)
+                        .show(ui, |ui| {
+                            if preview_tab == 0 {
+                                ui.monospace(hex_dump(bytes));
+                            } else {
+                                ui.monospace(&decoded);
+                            }
+                        });
+                } else {
+                    ui.columns(2, |cols| {
+                        egui::ScrollArea::vertical()
+                            .id_salt("hex_preview")
+                            .max_height(160.0)
+                            .show(&mut cols[0], |ui| {
+                                ui.monospace(hex_dump(bytes));
+                            });
+                        egui::ScrollArea::vertical()
+                            .id_salt("decoded_preview")
+                            .max_height(160.0)
+                            .show(&mut cols[1], |ui| {
+                                ui.monospace(&decoded);
+                            });
+                    });
+                }
+            });
+            self.preview_tab = preview_tab;
+        }
+
+        /* 换行统计: 混了多种换行就提供一键统一到多数派 */
+        let mut fix_eol = None;
+        if let Some(bytes) = &self.preview_bytes {
+            let decoded = decode_idx(self.from_idx, bytes);
+            let (crlf, lf, cr) = count_eols(&decoded);
+            if crlf + lf + cr > 0 {
+                ui.horizontal(|ui| {
+                    ui.weak(format!("CRLF {} · LF {} · CR {}", crlf, lf, cr));
+                    let kinds = [crlf, lf, cr].iter().filter(|n| **n > 0).count();
+                    if kinds > 1 {
+                        ui.colored_label(egui::Color32::YELLOW, t("mixed_eol", self.lang));
+                        if ui.button(t("fix_eol", self.lang)).clicked() {
+                            fix_eol = Some(if crlf >= lf && crlf >= cr {
+                                LineEnding::Crlf
+                            } else if lf >= cr {
+                                LineEnding::Lf
+                            } else {
+                                LineEnding::Cr
+                            });
+                        }
+                    }
+                });
+            }
+        }
+        if let Some(eol) = fix_eol {
+            self.eol = eol;
+        }
+
+        /* 疑似二进制的提示: 强转要用户自己点 */
+        if let Some((i, o)) = self.pending_binary.clone() {
+            ui.separator();
+            ui.colored_label(
+                egui::Color32::LIGHT_RED,
+                format!("{} {}", t("binary_warn", self.lang), i.display()),
+            );
+            ui.horizontal(|ui| {
+                if ui.button(t("force", self.lang)).clicked() {
+                    self.pending_binary = None;
+                    self.binary_force_once = true;
+                    self.start_file_job(i.clone(), o.clone());
+                }
+                if ui.button(t("cancel", self.lang)).clicked() {
+                    self.pending_binary = None;
+                    self.push_msg(MsgLevel::Warn, t("skipped", self.lang).into());
+                }
+            });
+        }
+
+        /* 输出即输入的警告: 确认后按原地转换走, 备份已预先勾上 */
+        if let Some((i, _)) = self.pending_same.clone() {
+            ui.separator();
+            ui.colored_label(
+                egui::Color32::LIGHT_RED,
+                format!("{} {}", t("same_path", self.lang), i.display()),
+            );
+            ui.horizontal(|ui| {
+                ui.checkbox(&mut self.backup, t("backup", self.lang));
+                if ui.button(t("commit", self.lang)).clicked() {
+                    self.pending_same = None;
+                    self.prepare_convert(i.clone(), i.clone());
+                }
+                if ui.button(t("cancel", self.lang)).clicked() {
+                    self.pending_same = None;
+                    self.push_msg(MsgLevel::Warn, t("skipped", self.lang).into());
+                }
+            });
+        }
+
+        /* Ask 策略的提示,不阻塞界面 */
+        if let Some((i, o)) = self.pending_conflict.clone() {
+            ui.separator();
+            ui.label(format!("{} {}", t("exists", self.lang), o.display()));
+            ui.horizontal(|ui| {
+                if ui.button(t("overwrite", self.lang)).clicked() {
+                    self.pending_conflict = None;
+                    self.prepare_convert(i.clone(), o.clone());
+                }
+                if ui.button(t("skip", self.lang)).clicked() {
+                    self.pending_conflict = None;
+                    self.push_msg(MsgLevel::Warn, t("skipped", self.lang).into());
+                }
+                if ui.button(t("rename", self.lang)).clicked() {
+                    self.pending_conflict = None;
+                    let renamed = renamed_path(&o);
+                    self.prepare_convert(i, renamed);
+                }
+            });
+        }
+
+        ui.separator();
+        for (name, p) in self.progress.values() {
+            ui.add(egui::ProgressBar::new(*p).text(name).show_percentage());
+        }
+        if !self.progress.is_empty() {
+            self.ui_throughput(ui);
+        }
+        self.ui_messages(ui);
+
+        /* 转完顺手打开, 不用去翻目录找 */
+        if let Some(out) = self.last_output.clone() {
+            ui.horizontal(|ui| {
+                if ui.button(t("open_file", self.lang)).clicked() {
+                    open_in_editor(&out);
+                }
+                if ui.button(t("open_folder", self.lang)).clicked() {
+                    open_in_folder(&out);
+                }
+            });
+        }
+
+        /* 最近任务: 一键重做 / 打开输出目录 */
+        if !self.history.is_empty() {
+            let mut repeat: Option<HistoryEntry> = None;
+            egui::CollapsingHeader::new(t("recent", self.lang)).show(ui, |ui| {
+                egui::ScrollArea::vertical()
+                    .id_salt("recent")
+                    .max_height(160.0)
+                    .show(ui, |ui| {
+                        for (i, entry) in self.history.iter().enumerate() {
+                            ui.horizontal(|ui| {
+                                ui.label(fmt_timestamp_lang(entry.secs, self.lang));
+                                ui.label(format!(
+                                    "{} -> {} ({} -> {})",
+                                    entry.input.display(),
+                                    entry.output.display(),
+                                    ENCODINGS[entry.from].1,
+                                    ENCODINGS[entry.to].1,
+                                ));
+                                ui.push_id(i, |ui| {
+                                    if ui.button(t("repeat", self.lang)).clicked() {
+                                        repeat = Some(entry.clone());
+                                    }
+                                    if ui.button(t("open_folder", self.lang)).clicked() {
+                                        open_in_folder(&entry.output);
+                                    }
+                                });
+                            });
+                        }
+                    });
+            });
+            if let Some(entry) = repeat {
+                self.from_idx = entry.from;
+                self.to_idx = entry.to;
+                self.start_file_job(entry.input, entry.output);
+            }
+        }
+    }
+
+    /* Ask 策略拦下的冲突队列: 逐个答复或勾上一键全答,
+    工人线程不等答复, 其它文件一直在转 */
+    fn ui_batch_conflicts(&mut self, ui: &mut egui::Ui) {
+        if self.batch_conflicts.is_empty() {
+            return;
+        }
+        ui.separator();
+        ui.horizontal(|ui| {
+            ui.label(format!(
+                "{} {}",
+                t("exists", self.lang),
+                fmt_count(self.batch_conflicts.len(), self.lang)
+            ));
+            ui.checkbox(&mut self.conflict_apply_all, t("apply_all", self.lang));
+        });
+        let mut act: Option<(usize, ConflictPolicy)> = None;
+        egui::ScrollArea::vertical()
+            .id_salt("batch_conflicts")
+            .max_height(160.0)
+            .show(ui, |ui| {
+                for (i, (_, label)) in self.batch_conflicts.iter().enumerate() {
+                    ui.horizontal(|ui| {
+                        ui.label(label);
+                        ui.push_id(("conflict", i), |ui| {
+                            for (policy, key) in [
+                                (ConflictPolicy::Overwrite, "overwrite"),
+                                (ConflictPolicy::Skip, "skip"),
+                                (ConflictPolicy::Rename, "rename"),
+                            ] {
+                                if ui.small_button(t(key, self.lang)).clicked() {
+                                    act = Some((i, policy));
+                                }
+                            }
+                        });
+                    });
+                }
+            });
+        if let Some((i, policy)) = act {
+            if self.conflict_apply_all {
+                for (job, label) in std::mem::take(&mut self.batch_conflicts) {
+                    self.resolve_conflict(job, label, policy);
+                }
+            } else {
+                let (job, label) = self.batch_conflicts.remove(i);
+                self.resolve_conflict(job, label, policy);
+            }
+        }
+    }
+
+    /* 答复后的冲突条目接回工作线程继续转, 结果仍走原来的通道 */
+    fn resolve_conflict(&mut self, mut job: FileJob, label: String, policy: ConflictPolicy) {
+        let Some(tx) = self.worker_tx.clone() else {
+            return;
+        };
+        match policy {
+            ConflictPolicy::Skip => {
+                tx.send(WorkerMsg::FileResult(
+                    LogLevel::Warn,
+                    label,
+                    "skipped".into(),
+                ))
+                .ok();
+                return;
+            }
+            ConflictPolicy::Rename => job.output = renamed_path(&job.output),
+            _ => {}
+        }
+        thread::spawn(move || {
+            if let Some(parent) = job.output.parent() {
+                std::fs::create_dir_all(parent).ok();
+            }
+            let (level, status) = transcode_file(job, &tx);
+            tx.send(WorkerMsg::FileResult(level, label, status)).ok();
+        });
+    }
+
+    fn ui_dir(&mut self, ui: &mut egui::Ui) {
+        /* 一次批量任务可以混合多个目录和单个文件 */
+        ui.horizontal(|ui| {
+            if ui.button(t("add_dir", self.lang)).clicked()
+                && let Some(d) = self.file_dialog().pick_folder()
+            {
+                self.last_dir = Some(d.clone());
+                self.batch_roots.push(d);
+            }
+            if ui.button(t("add_files", self.lang)).clicked()
+                && let Some(files) = self.file_dialog().pick_files()
+            {
+                if let Some(f) = files.first() {
+                    self.last_dir = f.parent().map(Path::to_path_buf);
+                }
+                self.batch_roots.extend(files);
+            }
+            /* 资源管理器里复制的文件/目录直接加入 */
+            if ui.button(t("paste_files", self.lang)).clicked() {
+                let files = clipboard_files();
+                if files.is_empty() {
+                    self.push_msg(MsgLevel::Warn, t("clip_no_files", self.lang).into());
+                } else {
+                    self.batch_roots.extend(files);
+                }
+            }
+            if !self.batch_roots.is_empty() && ui.button(t("clear_roots", self.lang)).clicked() {
+                self.batch_roots.clear();
+            }
+        });
+
+        let mut remove = None;
+        for (i, root) in self.batch_roots.iter().enumerate() {
+            ui.horizontal(|ui| {
+                ui.label(root.display().to_string());
+                ui.push_id(i, |ui| {
+                    if ui.small_button("✕").clicked() {
+                        remove = Some(i);
+                    }
+                });
+            });
+        }
+        if let Some(i) = remove {
+            self.batch_roots.remove(i);
+        }
+
+        ui.horizontal(|ui| {
+            if ui.button(t("select_outdir", self.lang)).clicked() {
+                self.output_dir = self.file_dialog().pick_folder();
+            }
+            if let Some(d) = &self.output_dir {
+                ui.label(d.display().to_string());
+            }
+        });
+
+        ui.horizontal(|ui| {
+            ui.label(t("pattern", self.lang));
+            ui.text_edit_singleline(&mut self.pattern);
+            ui.checkbox(&mut self.in_place, t("in_place", self.lang));
+            ui.checkbox(&mut self.pair_subs, t("pair_subs", self.lang));
+        });
+
+        /* 配对模式下常顺带加语言后缀 */
+        if self.pair_subs {
+            ui.horizontal(|ui| {
+                ui.label(t("sub_suffix", self.lang));
+                ui.text_edit_singleline(&mut self.sub_suffix);
+            });
+        }
+
+        if !self.in_place {
+            ui.horizontal(|ui| {
+                ui.label(t("name_tpl", self.lang));
+                ui.text_edit_singleline(&mut self.name_tpl);
+            });
+            /* 冲突策略: Ask 把撞名的条目停到下面排队, 其余文件照常转 */
+            ui.horizontal(|ui| {
+                ui.label(t("conflict", self.lang));
+                for (policy, key) in [
+                    (ConflictPolicy::Overwrite, "overwrite"),
+                    (ConflictPolicy::Skip, "skip"),
+                    (ConflictPolicy::Rename, "rename"),
+                    (ConflictPolicy::Ask, "ask"),
+                ] {
+                    ui.selectable_value(&mut self.conflict, policy, t(key, self.lang));
+                }
+            });
+        }
+
+        ui.horizontal(|ui| {
+            ui.checkbox(&mut self.strip_bom, t("strip_bom", self.lang));
+            ui.checkbox(&mut self.write_bom, t("write_bom", self.lang));
+            ui.checkbox(&mut self.sidecar, t("sidecar", self.lang));
+            ui.checkbox(&mut self.verify_after, t("verify", self.lang));
+            ui.checkbox(&mut self.auto_open, t("auto_open", self.lang));
+            ui.checkbox(&mut self.incremental, t("incremental", self.lang));
+            ui.separator();
+            ui.label(t("binary", self.lang));
+            for (policy, key) in [
+                (BinaryPolicy::Skip, "skip"),
+                (BinaryPolicy::Ask, "ask"),
+                (BinaryPolicy::Force, "force"),
+            ] {
+                ui.selectable_value(&mut self.binary_policy, policy, t(key, self.lang));
+            }
+        });
+
+        self.ui_fallback(ui);
+
+        self.ui_sandbox_settings(ui);
+
+        ui.horizontal(|ui| {
+            if (ui.button(t("start", self.lang)).clicked()
+                || std::mem::take(&mut self.start_requested))
+                && !self.batch_roots.is_empty()
+                && (self.in_place || self.output_dir.is_some())
+            {
+                self.results.clear();
+                self.start_batch_job();
+            }
+            /* 仅分析: 只读扫描, 不写任何文件 */
+            if ui.button(t("analyze_only", self.lang)).clicked() && !self.batch_roots.is_empty() {
+                self.analyze_rows.clear();
+                self.start_analyze_job();
+            }
+            /* 大迁移出了问题时按 .meta.json 旁记批量转回去 */
+            if ui.button(t("revert_meta", self.lang)).clicked() && !self.batch_roots.is_empty() {
+                self.results.clear();
+                self.start_revert_job();
+            }
+        });
+
+        self.ui_sandbox_prompt(ui);
+        self.ui_batch_conflicts(ui);
+
+        ui.separator();
+        for (name, p) in self.progress.values() {
+            ui.add(egui::ProgressBar::new(*p).text(name).show_percentage());
+        }
+        if !self.progress.is_empty() {
+            self.ui_throughput(ui);
+        }
+        self.ui_messages(ui);
+
+        if !self.results.is_empty() {
+            ui.separator();
+            ui.label(t("results", self.lang));
+            egui::ScrollArea::vertical().show(ui, |ui| {
+                egui::Grid::new("dir_results").striped(true).show(ui, |ui| {
+                    for (path, status) in &self.results {
+                        ui.label(path);
+                        ui.label(status);
+                        ui.end_row();
+                    }
+                });
+            });
+        }
+
+        if !self.analyze_rows.is_empty() {
+            ui.separator();
+            ui.horizontal(|ui| {
+                ui.label(t("analysis", self.lang));
+                if ui.button(t("export_csv", self.lang)).clicked()
+                    && let Some(path) = self.file_dialog().set_file_name("analysis.csv").save_file()
+                {
+                    match std::fs::write(&path, analyze_csv(&self.analyze_rows)) {
+                        Ok(_) => self.push_msg(MsgLevel::Info, format!("Done: {}", path.display())),
+                        Err(e) => self.push_msg(MsgLevel::Error, e.to_string()),
+                    }
+                }
+            });
+
+            /* 点表头排序, 再点一次反向 */
+            let mut sort_request = None;
+            egui::ScrollArea::vertical()
+                .id_salt("analyze_rows")
+                .max_height(240.0)
+                .show(ui, |ui| {
+                    egui::Grid::new("analyze_grid")
+                        .striped(true)
+                        .show(ui, |ui| {
+                            for (col, key) in [
+                                "col_file",
+                                "col_enc",
+                                "col_bom",
+                                "col_eol",
+                                "col_lossy",
+                                "col_snippet",
+                            ]
+                            .iter()
+                            .enumerate()
+                            {
+                                if ui.button(t(key, self.lang)).clicked() {
+                                    sort_request = Some(col);
+                                }
+                            }
+                            ui.end_row();
+
+                            let yes = t("yes", self.lang);
+                            let no = t("no", self.lang);
+                            for row in &self.analyze_rows {
+                                ui.label(&row.name);
+                                ui.label(row.encoding);
+                                ui.label(if row.bom { yes } else { no });
+                                ui.label(row.eol);
+                                ui.label(if row.lossy { yes } else { no });
+                                ui.weak(egui::RichText::new(&row.snippet).monospace());
+                                ui.end_row();
+                            }
+                        });
+                });
+
+            if let Some(col) = sort_request {
+                let desc = self.analyze_sort == (col, false);
+                self.analyze_sort = (col, desc);
+                self.analyze_rows.sort_by(|a, b| {
+                    let ord = match col {
+                        0 => a.name.cmp(&b.name),
+                        1 => a.encoding.cmp(b.encoding),
+                        2 => a.bom.cmp(&b.bom),
+                        3 => a.eol.cmp(b.eol),
+                        4 => a.lossy.cmp(&b.lossy),
+                        _ => a.snippet.cmp(&b.snippet),
+                    };
+                    if desc { ord.reverse() } else { ord }
+                });
+            }
+        }
+    }
+
+    fn start_analyze_job(&mut self) {
+        self.push_msg(MsgLevel::Progress, t("working", self.lang).into());
+        let (tx, rx) = mpsc::channel();
+        let tx = WorkerTx {
+            tx,
+            ctx: self.egui_ctx.clone(),
+            lane: 0,
+        };
+        let roots = self.batch_roots.clone();
+        let pattern = self.pattern.clone();
+        let to = self.to_idx;
+        let lang = self.lang;
+        self.rx = Some(rx);
+
+        thread::spawn(move || {
+            let mut total = 0;
+            for root in &roots {
+                /* 结果名带上根的名字, 多根时按根分组 */
+                let root_name = root
+                    .file_name()
+                    .map(|s| s.to_string_lossy().into_owned())
+                    .unwrap_or_default();
+
+                let files: Vec<(PathBuf, PathBuf)> = if root.is_file() {
+                    vec![(root.clone(), PathBuf::from(&root_name))]
+                } else {
+                    collect_files(root, &pattern)
+                        .into_iter()
+                        .map(|rel| (root.join(&rel), Path::new(&root_name).join(rel)))
+                        .collect()
+                };
+
+                for (path, label) in files {
+                    tx.send(WorkerMsg::Progress(
+                        tx.lane,
+                        label.display().to_string(),
+                        0.0,
+                    ))
+                    .ok();
+                    if let Some(row) = analyze_file(&path, &label, to) {
+                        tx.send(WorkerMsg::Analyze(row)).ok();
+                    }
+                    total += 1;
+                }
+            }
+
+            tx.send(WorkerMsg::Done(
+                LogLevel::Info,
+                format!(
+                    "{} {} {}",
+                    t("analyzed_n", lang),
+                    fmt_count(total, lang),
+                    t("files_n", lang)
+                ),
+            ))
+            .ok();
+        });
+    }
+
+    /* 批量回转: 只认 *.meta.json 旁记, 逐个转回原编码 */
+    fn start_revert_job(&mut self) {
+        self.push_msg(MsgLevel::Progress, t("working", self.lang).into());
+        let (tx, rx) = mpsc::channel();
+        let tx = WorkerTx {
+            tx,
+            ctx: self.egui_ctx.clone(),
+            lane: 0,
+        };
+        let roots = self.batch_roots.clone();
+        let lang = self.lang;
+        self.rx = Some(rx);
+
+        thread::spawn(move || {
+            let mut total = 0;
+            for root in &roots {
+                let files: Vec<PathBuf> = if root.is_file() {
+                    vec![root.clone()]
+                } else {
+                    collect_files(root, "*.meta.json")
+                        .into_iter()
+                        .map(|rel| root.join(rel))
+                        .collect()
+                };
+                for path in files {
+                    if !path.to_string_lossy().ends_with(".meta.json") {
+                        continue;
+                    }
+                    let label = path.display().to_string();
+                    tx.send(WorkerMsg::Progress(tx.lane, label.clone(), 0.0))
+                        .ok();
+                    match revert_with_sidecar(&path) {
+                        Ok((file, exact)) => {
+                            let status = if exact {
+                                format!("Done: {}", file.display())
+                            } else {
+                                format!("Done: {} ({})", file.display(), t("revert_inexact", lang))
+                            };
+                            tx.send(WorkerMsg::FileResult(
+                                LogLevel::Info,
+                                file.display().to_string(),
+                                status,
+                            ))
+                            .ok();
+                            total += 1;
+                        }
+                        Err(e) => {
+                            tx.send(WorkerMsg::FileResult(LogLevel::Error, label, e))
+                                .ok();
+                        }
+                    }
+                }
+            }
+            tx.send(WorkerMsg::Done(
+                LogLevel::Info,
+                format!(
+                    "{} {} {}",
+                    t("done_n", lang),
+                    fmt_count(total, lang),
+                    t("files_n", lang)
+                ),
+            ))
+            .ok();
+        });
+    }
+
+    fn start_batch_job(&mut self) {
+        /* 原地转换检查每个根, 否则检查输出目录 */
+        let targets: Vec<PathBuf> = if self.in_place {
+            self.batch_roots.clone()
+        } else {
+            self.output_dir.iter().cloned().collect()
+        };
+        for target in &targets {
+            if let Some(dir) = self.sandbox_violation(target) {
+                self.pending_sandbox = Some((dir, SandboxAction::Batch));
+                return;
+            }
+        }
+
+        self.push_msg(MsgLevel::Progress, t("working", self.lang).into());
+        let (tx, rx) = mpsc::channel();
+        let tx = WorkerTx {
+            tx,
+            ctx: self.egui_ctx.clone(),
+            lane: 0,
+        };
+        let roots = self.batch_roots.clone();
+        /* None 表示原地转换 */
+        let out_dir = if self.in_place {
+            None
+        } else {
+            self.output_dir.clone()
+        };
+        /* 配对模式忽略用户通配符, 只扫字幕扩展名 */
+        let pattern = if self.pair_subs {
+            "*.srt;*.ass;*.ssa".to_string()
+        } else {
+            self.pattern.clone()
+        };
+        let pair_subs = self.pair_subs;
+        let name_tpl = self.name_tpl.clone();
+        let conflict = self.conflict;
+        self.worker_tx = Some(tx.clone());
+        let lang = self.lang;
+        let incremental = self.incremental;
+        let template = FileJob {
+            input: PathBuf::new(),
+            output: PathBuf::new(),
+            from: self.from_idx,
+            to: self.to_idx,
+            strip_bom: self.strip_bom,
+            write_bom: self.write_bom,
+            eol: self.eol,
+            backup: self.backup,
+            password: self.zip_password.clone(),
+            sub_suffix: self.sub_suffix.trim().to_string(),
+            sidecar: self.sidecar,
+            verify: self.verify_after,
+            binary: if std::mem::take(&mut self.binary_force_once) {
+                BinaryPolicy::Force
+            } else {
+                self.binary_policy
+            },
+            chain: self.fallback_chain.clone(),
+            lang: self.lang,
+        };
+        self.rx = Some(rx);
+
+        thread::spawn(move || {
+            let to_label = ENCODINGS[template.to].1;
+            /* 先把所有根展开成任务列表, 报告里的名字带根名分组;
+            第三项是增量库要回写的 (根序号, 键) */
+            let mut jobs: Vec<BatchItem> = Vec::new();
+            for (ri, root) in roots.iter().enumerate() {
+                let root_name = root.file_name().unwrap_or(root.as_os_str()).to_os_string();
+                let prefix = if roots.len() > 1 {
+                    format!("{}/", root_name.to_string_lossy())
+                } else {
+                    String::new()
+                };
+
+                if root.is_file() {
+                    let mut job = template.clone();
+                    job.input = root.clone();
+                    job.output = match &out_dir {
+                        None => root.clone(),
+                        Some(d) => d.join(apply_name_template(&name_tpl, Path::new(&root_name))),
+                    };
+                    jobs.push((job, root_name.to_string_lossy().into_owned(), None));
+                    continue;
+                }
+
+                /* 增量模式: 指纹没变的跳过, 变了且退回旧编码的标出来 */
+                let db = if incremental {
+                    load_project_db(root)
+                } else {
+                    std::collections::HashMap::new()
+                };
+
+                /* 多根输出时各自归入 输出目录/根名/ 避免相互覆盖 */
+                let base = match &out_dir {
+                    None => root.clone(),
+                    Some(d) if roots.len() > 1 => d.join(&root_name),
+                    Some(d) => d.clone(),
+                };
+                for rel in collect_files(root, &pattern) {
+                    let mut job = template.clone();
+                    job.input = root.join(&rel);
+                    if pair_subs && !has_matching_video(&job.input) {
+                        continue;
+                    }
+                    let label = format!("{}{}", prefix, rel.display());
+                    let key = rel.display().to_string().replace('\\', "/");
+                    if let Some(entry) = db.get(&key) {
+                        let data = std::fs::read(&job.input).unwrap_or_default();
+                        if fnv1a64(&data) == entry.fnv {
+                            tx.send(WorkerMsg::FileResult(
+                                LogLevel::Info,
+                                label,
+                                t("unchanged", lang).to_string(),
+                            ))
+                            .ok();
+                            continue;
+                        }
+                        let head = &data[..data.len().min(DETECT_LEN)];
+                        let det = detect_encoding_for(&job.input, head);
+                        if det.name() != entry.encoding {
+                            tx.send(WorkerMsg::FileResult(
+                                LogLevel::Warn,
+                                label.clone(),
+                                format!("{}: {}", t("regressed", lang), det.name()),
+                            ))
+                            .ok();
+                        }
+                    }
+                    job.output = match &out_dir {
+                        None => base.join(&rel),
+                        Some(_) => base.join(apply_name_template(&name_tpl, &rel)),
+                    };
+                    jobs.push((job, label, Some((ri, key))));
+                }
+            }
+            let total = jobs.len();
+
+            /* 总字节数先报一次, 界面好算吞吐和剩余时间 */
+            let total_bytes: u64 = jobs
+                .iter()
+                .map(|(job, _, _)| std::fs::metadata(&job.input).map(|m| m.len()).unwrap_or(0))
+                .sum();
+            tx.send(WorkerMsg::TotalBytes(total_bytes, Instant::now()))
+                .ok();
+
+            /* 小文件走快车道: 大量小文件先完成, 报告早点可用,
+            大文件在另一条工人线程上慢慢转 */
+            let (small, large): (Vec<_>, Vec<_>) = jobs.into_iter().partition(|(job, _, _)| {
+                std::fs::metadata(&job.input).is_ok_and(|m| m.len() < SMALL_FILE_LIMIT)
+            });
+
+            let run = move |lane: Vec<BatchItem>, tx: WorkerTx| -> Vec<(usize, String, PathBuf)> {
+                let mut converted = Vec::new();
+                for (mut job, label, tag) in lane {
+                    let size = std::fs::metadata(&job.input).map(|m| m.len()).unwrap_or(0);
+                    /* 输出已存在时按冲突策略处理, 原地转换不算冲突 */
+                    if job.output != job.input && job.output.exists() {
+                        match conflict {
+                            ConflictPolicy::Skip => {
+                                tx.send(WorkerMsg::FileResult(
+                                    LogLevel::Warn,
+                                    label,
+                                    "skipped".into(),
+                                ))
+                                .ok();
+                                /* 跳过的字节也计入, 剩余时间才不会虚高 */
+                                tx.send(WorkerMsg::Bytes(size, Instant::now())).ok();
+                                continue;
+                            }
+                            ConflictPolicy::Rename => job.output = renamed_path(&job.output),
+                            /* Ask: 条目停到界面排队, 不挡后面的文件 */
+                            ConflictPolicy::Ask => {
+                                tx.send(WorkerMsg::Conflict(Box::new(job), label)).ok();
+                                tx.send(WorkerMsg::Bytes(size, Instant::now())).ok();
+                                continue;
+                            }
+                            ConflictPolicy::Overwrite => {}
+                        }
+                    }
+                    if let Some(parent) = job.output.parent() {
+                        std::fs::create_dir_all(parent).ok();
+                    }
+                    let input = job.input.clone();
+                    let (level, status) = transcode_file(job, &tx);
+                    /* 成功才值得记指纹, 失败下次还得再试 */
+                    if let Some((ri, key)) = tag
+                        && level == LogLevel::Info
+                    {
+                        converted.push((ri, key, input));
+                    }
+                    tx.send(WorkerMsg::FileResult(level, label, status)).ok();
+                    tx.send(WorkerMsg::Bytes(size, Instant::now())).ok();
+                }
+                converted
+            };
+
+            /* 快车道的进度占第二行, 两条车道互不覆盖 */
+            let mut tx_small = tx.clone();
+            tx_small.lane = 1;
+            let fast_lane = thread::spawn(move || run(small, tx_small));
+            let mut converted = run(large, tx.clone());
+            converted.extend(fast_lane.join().unwrap_or_default());
+
+            /* 转完把各根的指纹库写回, 原地模式读到的就是新字节 */
+            if incremental {
+                for (ri, root) in roots.iter().enumerate() {
+                    if !root.is_dir() {
+                        continue;
+                    }
+                    let mut db = load_project_db(root);
+                    let mut touched = false;
+                    for (i, key, input) in &converted {
+                        if *i == ri
+                            && let Ok(data) = std::fs::read(input)
+                        {
+                            db.insert(
+                                key.clone(),
+                                DbEntry {
+                                    encoding: to_label.to_string(),
+                                    fnv: fnv1a64(&data),
+                                },
+                            );
+                            touched = true;
+                        }
+                    }
+                    if touched {
+                        save_project_db(root, &db);
+                    }
+                }
+            }
+
+            tx.send(WorkerMsg::Done(
+                LogLevel::Info,
+                format!(
+                    "{} {} {}",
+                    t("done_n", lang),
+                    fmt_count(total, lang),
+                    t("files_n", lang)
+                ),
+            ))
+            .ok();
+        });
+    }
+
+    /* 解码进会话缓存, 已有且文件没变就直接复用 */
+    fn ensure_decoded(&mut self, path: &Path) -> Result<(), String> {
+        let mtime = std::fs::metadata(path).and_then(|m| m.modified()).ok();
+        if let Some(c) = &self.decode_cache
+            && c.path == path
+            && c.from == self.from_idx
+            && c.mtime == mtime
+        {
+            return Ok(());
+        }
+        let data = std::fs::read(path).map_err(|e| e.to_string())?;
+        let text = decode_idx(self.from_idx, strip_bom_idx(&data, self.from_idx));
+        self.decode_cache = Some(DecodeCache {
+            path: path.to_path_buf(),
+            mtime,
+            from: self.from_idx,
+            text: std::rc::Rc::new(text),
+        });
+        Ok(())
+    }
+
+    /* 冲突处理过后进入对比预览; 文件读不了就直接转,由任务自己报错 */
+    fn prepare_convert(&mut self, input: PathBuf, output: PathBuf) {
+        /* 疑似二进制先按策略拦下来, Ask 弹确认条 */
+        if self.binary_policy != BinaryPolicy::Force
+            && binary_check_applies(self.from_idx)
+            && read_preview(&input).is_some_and(|d| looks_binary(&d))
+        {
+            match self.binary_policy {
+                BinaryPolicy::Skip => {
+                    self.push_msg(
+                        MsgLevel::Warn,
+                        format!("{}: {}", input.display(), t("skipped_binary", self.lang)),
+                    );
+                }
+                _ => self.pending_binary = Some((input, output)),
+            }
+            return;
+        }
+        if self.ensure_decoded(&input).is_err() {
+            self.start_file_job(input, output);
+            return;
+        }
+        let text = std::rc::Rc::clone(&self.decode_cache.as_ref().unwrap().text);
+        match build_compare(&input, &output, &text, self.to_idx) {
+            Some(pending) => self.pending_convert = Some(pending),
+            None => self.start_file_job(input, output),
+        }
+    }
+
+    /* 菜单和 Ctrl+O 共用的打开逻辑 */
+    /* 把界面上的文本字段存回当前标签页快照 */
+    fn stash_doc(&mut self) {
+        let d = &mut self.docs[self.doc_idx];
+        d.input = self.input_text.clone();
+        d.output = self.output_text.clone();
+        d.from = self.from_idx;
+        d.to = self.to_idx;
+    }
+
+    fn switch_doc(&mut self, i: usize) {
+        if i == self.doc_idx || i >= self.docs.len() {
+            return;
+        }
+        self.stash_doc();
+        let d = &self.docs[i];
+        self.input_text = d.input.clone();
+        self.output_text = d.output.clone();
+        self.from_idx = d.from;
+        self.to_idx = d.to;
+        self.doc_idx = i;
+        /* 重新防抖转一遍, 报告跟着当前页走 */
+        self.live_edit = Some(Instant::now());
+    }
+
+    /* 交换来源/目标编码; 文本模式顺带把输出搬回输入, 方便来回试 */
+    fn swap_encodings(&mut self) {
+        std::mem::swap(&mut self.from_idx, &mut self.to_idx);
+        if self.mode == Mode::Text && !self.output_text.is_empty() {
+            self.input_text = std::mem::take(&mut self.output_text);
+            self.live_edit = Some(Instant::now());
+        }
+    }
+
+    fn open_input(&mut self) {
+        if let Some(p) = self.file_dialog().pick_file() {
+            self.mode = Mode::File;
+            self.preview_bytes = read_preview(&p);
+            self.last_dir = p.parent().map(Path::to_path_buf);
+            if !self.in_place && self.suffix_output {
+                self.output_file = Some(suggested_output(&p, self.to_idx));
+            }
+            self.input_file = Some(p);
+        }
+    }
+
+    /* 文本模式的输出另存为文件 */
+    fn save_output(&mut self) {
+        if self.output_text.is_empty() {
+            return;
+        }
+        if let Some(p) = self.file_dialog().save_file() {
+            match std::fs::write(&p, &self.output_text) {
+                Ok(_) => self.push_msg(MsgLevel::Info, format!("Done: {}", p.display())),
+                Err(e) => self.push_msg(
+                    MsgLevel::Error,
+                    TranscodeError::from_io(&p, e).message(self.lang),
+                ),
+            }
+        }
+    }
+
+    fn ui_menu(&mut self, ui: &mut egui::Ui) {
+        egui::MenuBar::new().ui(ui, |ui| {
+            ui.menu_button(t("menu_file", self.lang), |ui| {
+                if ui.button(t("open", self.lang)).clicked() {
+                    self.open_input();
+                    ui.close();
+                }
+                if ui.button(t("save_out", self.lang)).clicked() {
+                    self.save_output();
+                    ui.close();
+                }
+                if ui.button(t("quit", self.lang)).clicked() {
+                    ui.ctx().send_viewport_cmd(egui::ViewportCommand::Close);
+                }
+            });
+            ui.menu_button(t("menu_edit", self.lang), |ui| {
+                if ui.button(t("paste_clip", self.lang)).clicked() {
+                    if let Some(text) = clipboard_text() {
+                        self.mode = Mode::Text;
+                        self.input_text = text;
+                        self.live_edit = Some(Instant::now());
+                    }
+                    ui.close();
+                }
+                if ui.button(t("copy_out", self.lang)).clicked() {
+                    ui.ctx().copy_text(self.output_text.clone());
+                    ui.close();
+                }
+                if ui.button(t("swap", self.lang)).clicked() {
+                    self.swap_encodings();
+                    ui.close();
+                }
+                if ui.button(t("char_map", self.lang)).clicked() {
+                    self.show_charmap = !self.show_charmap;
+                    ui.close();
+                }
+            });
+            ui.menu_button(t("menu_mode", self.lang), |ui| {
+                for (mode, key) in [
+                    (Mode::Text, "text"),
+                    (Mode::File, "file"),
+                    (Mode::Dir, "dir"),
+                    (Mode::Names, "names"),
+                    (Mode::Repair, "repair"),
+                    (Mode::Playground, "playground"),
+                ] {
+                    if ui
+                        .selectable_value(&mut self.mode, mode, t(key, self.lang))
+                        .clicked()
+                    {
+                        ui.close();
+                    }
+                }
+            });
+        });
+    }
+
+    /* 进行中的消息只留最新一条; 其余排队, 超出上限挤掉最旧的 */
+    fn push_msg(&mut self, level: MsgLevel, text: String) {
+        self.messages.retain(|m| m.level != MsgLevel::Progress);
+        self.messages.push(StatusMsg { level, text });
+        if self.messages.len() > MSG_LIMIT {
+            self.messages.remove(0);
+        }
+    }
+
+    /* 退路链编辑: 有序列表, 上移/删除/追加 */
+    fn ui_fallback(&mut self, ui: &mut egui::Ui) {
+        egui::CollapsingHeader::new(t("fallback", self.lang)).show(ui, |ui| {
+            ui.weak(t("fallback_hint", self.lang));
+            let mut up = None;
+            let mut remove = None;
+            for (i, idx) in self.fallback_chain.iter().enumerate() {
+                ui.horizontal(|ui| {
+                    ui.label(format!("{}. {}", i + 1, ENCODINGS[*idx].1));
+                    if i > 0 && ui.small_button("⬆").clicked() {
+                        up = Some(i);
+                    }
+                    if ui.small_button("✖").clicked() {
+                        remove = Some(i);
+                    }
+                });
+            }
+            if let Some(i) = up {
+                self.fallback_chain.swap(i, i - 1);
+            }
+            if let Some(i) = remove {
+                self.fallback_chain.remove(i);
+            }
+            ui.horizontal(|ui| {
+                encoding_combo(ui, "fallback_add", &mut self.fallback_add);
+                if ui.button("＋").clicked() && !self.fallback_chain.contains(&self.fallback_add) {
+                    self.fallback_chain.push(self.fallback_add);
+                }
+            });
+        });
+    }
+
+    /* 吞吐量和预计剩余时间, 跟在进度条下面 */
+    fn ui_throughput(&self, ui: &mut egui::Ui) {
+        let Some((start, last, done, total)) = self.byte_progress else {
+            return;
+        };
+        let elapsed = last.duration_since(start).as_secs_f64();
+        /* 刚开头的几个样本抖得厉害, 先不显示 */
+        if done == 0 || elapsed < 0.2 {
+            return;
+        }
+        let rate = done as f64 / elapsed;
+        let mut text = format!("{}/s", fmt_size(rate as u64));
+        if total > done && rate > 0.0 {
+            let eta = (total - done) as f64 / rate;
+            text.push_str(&format!(
+                " · {} {}",
+                t("eta", self.lang),
+                fmt_duration(eta.ceil() as u64)
+            ));
+        }
+        ui.weak(text);
+    }
+
+    /* 状态消息列表: 每条带图标颜色, 可单独点掉或全部关闭 */
+    fn ui_messages(&mut self, ui: &mut egui::Ui) {
+        if self.messages.is_empty() {
+            ui.weak(t("idle", self.lang));
+            return;
+        }
+        let mut dismiss = None;
+        for (i, m) in self.messages.iter().enumerate() {
+            ui.horizontal(|ui| {
+                ui.colored_label(
+                    m.level.color(ui.visuals()),
+                    format!("{} {}", m.level.icon(), m.text),
+                );
+                if m.level != MsgLevel::Progress && ui.small_button("✖").clicked() {
+                    dismiss = Some(i);
+                }
+            });
+        }
+        if let Some(i) = dismiss {
+            self.messages.remove(i);
+        }
+        if self.messages.len() > 1 && ui.small_button(t("dismiss_all", self.lang)).clicked() {
+            self.messages.clear();
+        }
+    }
+
+    /* 底部日志面板: 按级别过滤 + 复制全部 */
+    fn ui_log(&mut self, ui: &mut egui::Ui) {
+        if self.log.is_empty() {
+            return;
+        }
+        ui.separator();
+        egui::CollapsingHeader::new(t("log", self.lang)).show(ui, |ui| {
+            ui.horizontal(|ui| {
+                for (level, key) in [
+                    (LogLevel::Info, "log_all"),
+                    (LogLevel::Warn, "log_warn"),
+                    (LogLevel::Error, "log_err"),
+                ] {
+                    ui.selectable_value(&mut self.log_min, level, t(key, self.lang));
+                }
+                if ui.button(t("copy_log", self.lang)).clicked() {
+                    let all: Vec<String> = self
+                        .log
+                        .iter()
+                        .map(|e| {
+                            format!("{} [{}] {}", fmt_timestamp(e.secs), e.level.tag(), e.text)
+                        })
+                        .collect();
+                    ui.ctx().copy_text(all.join("\n"));
+                }
+            });
+            egui::ScrollArea::vertical()
+                .id_salt("log")
+                .max_height(160.0)
+                .stick_to_bottom(true)
+                .show(ui, |ui| {
+                    for entry in &self.log {
+                        if entry.level.rank() < self.log_min.rank() {
+                            continue;
+                        }
+                        let color = match entry.level {
+                            LogLevel::Info => ui.visuals().text_color(),
+                            LogLevel::Warn => egui::Color32::YELLOW,
+                            LogLevel::Error => egui::Color32::LIGHT_RED,
+                        };
+                        ui.colored_label(
+                            color,
+                            format!(
+                                "{} {}",
+                                fmt_timestamp_lang(entry.secs, self.lang),
+                                entry.text
+                            ),
+                        );
+                    }
+                });
+        });
+    }
+
+    /* 信任目录检查: 返回被拦截的目标目录, None 表示放行 */
+    fn sandbox_violation(&self, target: &Path) -> Option<PathBuf> {
+        if !self.sandbox || self.sandbox_once {
+            return None;
+        }
+        let dir = if target.is_dir() {
+            target
+        } else {
+            target.parent().unwrap_or(target)
+        };
+        if self.trusted_dirs.iter().any(|t| dir.starts_with(t)) {
+            None
+        } else {
+            Some(dir.to_path_buf())
+        }
+    }
+
+    fn ui_sandbox_settings(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            ui.checkbox(&mut self.sandbox, t("sandbox", self.lang));
+            if self.sandbox
+                && ui.button(t("add_trusted", self.lang)).clicked()
+                && let Some(d) = self.file_dialog().pick_folder()
+            {
+                self.trusted_dirs.push(d);
+            }
+        });
+        if self.sandbox && !self.trusted_dirs.is_empty() {
+            let mut remove = None;
+            ui.label(t("trusted_dirs", self.lang));
+            for (i, dir) in self.trusted_dirs.iter().enumerate() {
+                ui.horizontal(|ui| {
+                    ui.label(dir.display().to_string());
+                    ui.push_id(("trusted", i), |ui| {
+                        if ui.small_button("✕").clicked() {
+                            remove = Some(i);
+                        }
+                    });
+                });
+            }
+            if let Some(i) = remove {
+                self.trusted_dirs.remove(i);
+            }
+        }
+    }
+
+    /* 被拦截任务的提示: 信任该目录 / 本次运行放行 / 取消 */
+    fn ui_sandbox_prompt(&mut self, ui: &mut egui::Ui) {
+        let Some((dir, _)) = &self.pending_sandbox else {
+            return;
+        };
+        ui.separator();
+        ui.colored_label(
+            egui::Color32::LIGHT_RED,
+            format!("{} {}", t("sandbox_blocked", self.lang), dir.display()),
+        );
+        let mut resume = false;
+        ui.horizontal(|ui| {
+            if ui.button(t("trust_dir", self.lang)).clicked()
+                && let Some((dir, _)) = &self.pending_sandbox
+            {
+                self.trusted_dirs.push(dir.clone());
+                resume = true;
+            }
+            if ui.button(t("allow_once", self.lang)).clicked() {
+                self.sandbox_once = true;
+                resume = true;
+            }
+            if ui.button(t("cancel", self.lang)).clicked() {
+                self.pending_sandbox = None;
+                self.push_msg(MsgLevel::Warn, t("skipped", self.lang).into());
+            }
+        });
+        if resume && let Some((_, action)) = self.pending_sandbox.take() {
+            match action {
+                SandboxAction::Single(i, o) => self.start_file_job(i, o),
+                SandboxAction::Batch => self.start_batch_job(),
+                SandboxAction::Rename => self.apply_rename(),
+                SandboxAction::UndoRename => self.undo_rename(),
+            }
+        }
+    }
+
+    /* 字符表弹窗: 名字/码点过滤, 点一下插进文本输入框 */
+    fn ui_charmap(&mut self, ctx: &egui::Context) {
+        if !self.show_charmap {
+            return;
+        }
+        let mut open = true;
+        let mut insert = None;
+        egui::Window::new(t("char_map", self.lang))
+            .open(&mut open)
+            .default_width(280.0)
+            .show(ctx, |ui| {
+                ui.text_edit_singleline(&mut self.charmap_query)
+                    .on_hover_text(t("char_search", self.lang));
+                let query = self.charmap_query.trim().to_ascii_lowercase();
+
+                /* 直接敲码点时第一个就是那个字符 */
+                if let Some(c) = charmap_lookup(&query) {
+                    let label = if char_visible(c) {
+                        format!("{}  U+{:04X}", c, c as u32)
+                    } else {
+                        format!("U+{:04X}", c as u32)
+                    };
+                    if ui.button(label).clicked() {
+                        insert = Some(c);
+                    }
+                    ui.separator();
+                }
+
+                egui::ScrollArea::vertical()
+                    .id_salt("charmap")
+                    .max_height(240.0)
+                    .show(ui, |ui| {
+                        ui.horizontal_wrapped(|ui| {
+                            for (name, c) in CHAR_MAP {
+                                if !query.is_empty() && !name.contains(&query) {
+                                    continue;
+                                }
+                                let label = if char_visible(*c) {
+                                    c.to_string()
+                                } else {
+                                    format!("U+{:04X}", *c as u32)
+                                };
+                                if ui
+                                    .button(egui::RichText::new(label).monospace())
+                                    .on_hover_text(format!("{} (U+{:04X})", name, *c as u32))
+                                    .clicked()
+                                {
+                                    insert = Some(*c);
+                                }
+                            }
+                        });
+                    });
+            });
+        if let Some(c) = insert {
+            self.mode = Mode::Text;
+            self.input_text.push(c);
+            self.live_edit = Some(Instant::now());
+        }
+        self.show_charmap = open;
+    }
+
+    fn start_file_job(&mut self, input: PathBuf, output: PathBuf) {
+        if let Some(dir) = self.sandbox_violation(&output) {
+            self.pending_sandbox = Some((dir, SandboxAction::Single(input, output)));
+            return;
+        }
+        self.push_msg(MsgLevel::Progress, t("working", self.lang).into());
+        let (tx, rx) = mpsc::channel();
+        let tx = WorkerTx {
+            tx,
+            ctx: self.egui_ctx.clone(),
+            lane: 0,
+        };
+        let job = FileJob {
+            input,
+            output,
+            from: self.from_idx,
+            to: self.to_idx,
+            strip_bom: self.strip_bom,
+            write_bom: self.write_bom,
+            eol: self.eol,
+            backup: self.backup,
+            password: self.zip_password.clone(),
+            sub_suffix: self.sub_suffix.trim().to_string(),
+            sidecar: self.sidecar,
+            verify: self.verify_after,
+            binary: if std::mem::take(&mut self.binary_force_once) {
+                BinaryPolicy::Force
+            } else {
+                self.binary_policy
+            },
+            chain: self.fallback_chain.clone(),
+            lang: self.lang,
+        };
+        self.rx = Some(rx);
+
+        thread::spawn(move || {
+            let size = std::fs::metadata(&job.input).map(|m| m.len()).unwrap_or(0);
+            tx.send(WorkerMsg::TotalBytes(size, Instant::now())).ok();
+            let meta = job.clone();
+            let (level, result) = transcode_file(job, &tx);
+            tx.send(WorkerMsg::Bytes(size, Instant::now())).ok();
+            tx.send(WorkerMsg::History(
+                level,
+                HistoryEntry {
+                    secs: now_secs(),
+                    from: meta.from,
+                    to: meta.to,
+                    input: meta.input,
+                    output: meta.output,
+                    result: result.clone(),
+                },
+            ))
+            .ok();
+            tx.send(WorkerMsg::Done(level, result)).ok();
+        });
+    }
+}
+
+/* ======================= 编码下拉 ======================= */
+fn encoding_combo(ui: &mut egui::Ui, id: &str, value: &mut usize) {
+    egui::ComboBox::from_id_salt(id)
+        .selected_text(ENCODINGS[*value].1)
+        .show_ui(ui, |ui| {
+            for (i, (_, label)) in ENCODINGS.iter().enumerate() {
+                ui.selectable_value(value, i, *label);
+            }
+        });
+}
+
+/* ======================= 换行符下拉 ======================= */
+fn eol_combo(ui: &mut egui::Ui, lang: Language, value: &mut LineEnding) {
+    let label = |v: LineEnding| match v {
+        LineEnding::Keep => t("keep", lang),
+        LineEnding::Lf => "LF",
+        LineEnding::Crlf => "CRLF",
+        LineEnding::Cr => "CR",
+    };
+
+    egui::ComboBox::from_id_salt("eol")
+        .selected_text(label(*value))
+        .show_ui(ui, |ui| {
+            for v in [
+                LineEnding::Keep,
+                LineEnding::Lf,
+                LineEnding::Crlf,
+                LineEnding::Cr,
+            ] {
+                ui.selectable_value(value, v, label(v));
+            }
+        });
+}
+
+/* ======================= 字体 ======================= */
+/*
+    预览里会出现泰文、阿拉伯文、emoji 等内嵌 CJK 字体
+    覆盖不到的脚本,按平台探测几个常见系统字体挂在
+    回退链末尾,找不到就跳过
+*/
+fn system_font_candidates() -> &'static [&'static str] {
+    if cfg!(target_os = "windows") {
+        &[
+            r"C:\Windows\Fonts\msyh.ttc",
+            r"C:\Windows\Fonts\seguiemj.ttf",
+            r"C:\Windows\Fonts\seguisym.ttf",
+            r"C:\Windows\Fonts\arial.ttf",
+        ]
+    } else if cfg!(target_os = "macos") {
+        &[
+            "/System/Library/Fonts/PingFang.ttc",
+            "/System/Library/Fonts/Supplemental/Arial Unicode.ttf",
+            "/System/Library/Fonts/Apple Symbols.ttf",
+        ]
+    } else {
+        &[
+            "/usr/share/fonts/opentype/noto/NotoSansCJK-Regular.ttc",
+            "/usr/share/fonts/truetype/noto/NotoSansSymbols-Regular.ttf",
+            "/usr/share/fonts/truetype/dejavu/DejaVuSans.ttf",
+        ]
+    }
+}
+
+fn setup_fonts(ctx: &egui::Context) {
+    let mut fonts = egui::FontDefinitions::default();
+    fonts
+        .font_data
+        .insert("cjk".into(), Arc::new(egui::FontData::from_static(FONT)));
+    fonts
+        .families
+        .get_mut(&egui::FontFamily::Proportional)
+        .unwrap()
+        .insert(0, "cjk".into());
+    fonts
+        .families
+        .get_mut(&egui::FontFamily::Monospace)
+        .unwrap()
+        .insert(0, "cjk".into());
+
+    /* 系统字体作为回退链,排在默认字体之后 */
+    for (i, path) in system_font_candidates().iter().enumerate() {
+        let Ok(data) = std::fs::read(path) else {
+            continue;
+        };
+        let name = format!("fallback{}", i);
+        fonts
+            .font_data
+            .insert(name.clone(), Arc::new(egui::FontData::from_owned(data)));
+        for family in [egui::FontFamily::Proportional, egui::FontFamily::Monospace] {
+            fonts.families.get_mut(&family).unwrap().push(name.clone());
+        }
+    }
+
+    ctx.set_fonts(fonts);
+}
+
+/* ======================= main ======================= */
+fn main() -> Result<(), eframe::Error> {
+    let mut args = std::env::args().skip(1).peekable();
+    match args.peek().map(String::as_str) {
+        Some("--serve") => {
+            args.next();
+            let port = match args.peek().and_then(|p| p.parse().ok()) {
+                Some(p) => {
+                    args.next();
+                    p
+                }
+                None => DEFAULT_PORT,
+            };
+            let limits = ServerLimits::from_args(args);
+            if let Err(e) = run_server(port, limits) {
+                eprintln!("serve failed: {}", e);
+            }
+            return Ok(());
+        }
+        /* 只读检查压缩包, 输出 JSON */
+        Some("inspect") => {
+            args.next();
+            let mut password = String::new();
+            let mut target = None;
+            while let Some(arg) = args.next() {
+                if arg == "--password" {
+                    password = args.next().unwrap_or_default();
+                } else {
+                    target = Some(arg);
+                }
+            }
+            let code = match target {
+                Some(p) => match inspect_zip(Path::new(&p), &password) {
+                    Ok(json) => {
+                        println!("{}", json);
+                        0
+                    }
+                    Err(e) => {
+                        eprintln!("inspect failed: {}", e);
+                        1
+                    }
+                },
+                None => {
+                    eprintln!("usage: inspect [--password <pwd>] <archive.zip>");
+                    2
+                }
+            };
+            std::process::exit(code);
+        }
+        /* 注册 / 取消资源管理器右键菜单 */
+        Some("--register") | Some("--unregister") => {
+            let register = args.next().as_deref() == Some("--register");
+            let result = if register {
+                register_shell_menu()
+            } else {
+                unregister_shell_menu()
+            };
+            let code = match result {
+                Ok(_) => {
+                    println!(
+                        "{}",
+                        if register {
+                            "registered"
+                        } else {
+                            "unregistered"
+                        }
+                    );
+                    0
+                }
+                Err(e) => {
+                    eprintln!("shell menu: {}", e);
+                    1
+                }
+            };
+            std::process::exit(code);
+        }
+        /* 右键菜单进来的无界面批量转换; --json 给流水线和钩子用 */
+        Some("--to-utf8") => {
+            args.next();
+            let mut json = false;
+            let mut password = String::new();
+            let mut files: Vec<PathBuf> = Vec::new();
+            while let Some(arg) = args.next() {
+                match arg.as_str() {
+                    "--json" => json = true,
+                    "--password" => password = args.next().unwrap_or_default(),
+                    _ => files.push(PathBuf::from(arg)),
+                }
+            }
+            if files.is_empty() {
+                eprintln!("usage: --to-utf8 [--json] [--password <pwd>] <files...>");
+                std::process::exit(2);
+            }
+            let mut lines = Vec::new();
+            let mut failed = false;
+            for f in &files {
+                let started = Instant::now();
+                let outcome = convert_to_utf8(f, &password);
+                if outcome.is_err() {
+                    failed = true;
+                }
+                if json {
+                    println!(
+                        "{}",
+                        cli_json_record(f, &outcome, started.elapsed().as_millis())
+                    );
+                } else {
+                    lines.push(match outcome {
+                        Ok(o) if o.binary => format!("{}: looks binary, skipped", f.display()),
+                        Ok(o) if o.skipped => format!("{}: already UTF-8", f.display()),
+                        Ok(o) => format!("{}: {} -> UTF-8", f.display(), o.encoding),
+                        Err(e) => format!("{}: {}", f.display(), e),
+                    });
+                }
+            }
+            if !json {
+                let summary = lines.join("\n");
+                println!("{}", summary);
+                shell_toast(&summary);
+            }
+            /* 0 全成, 1 部分失败, 2 用法错误 */
+            std::process::exit(if failed { 1 } else { 0 });
+        }
+        /* 无界面管道模式: stdin -> stdout 流式转码 */
+        Some("--from") | Some("--to") => {
+            let code = match pipe_encodings(args) {
+                Ok((from, to)) => match run_pipe(from, to) {
+                    Ok(_) => 0,
+                    Err(e) => {
+                        eprintln!("pipe failed: {}", e);
+                        1
+                    }
+                },
+                Err(e) => {
+                    eprintln!("{}", e);
+                    2
+                }
+            };
+            std::process::exit(code);
+        }
+        _ => {}
+    }
+
+    let launch = LaunchArgs::parse(args);
+
     let icon = from_png_bytes(ICON).unwrap();
 
     let opts = eframe::NativeOptions {
@@ -313,7 +7919,11 @@ fn main() -> Result<(), eframe::Error> {
         opts,
         Box::new(|cc| {
             setup_fonts(&cc.egui_ctx);
-            Ok(Box::new(CodeTransApp::default()))
+            let mut app = CodeTransApp::from_storage(cc.storage);
+            app.apply_launch(launch);
+            apply_theme(&cc.egui_ctx, app.theme, app.color_mode, app.font_scale);
+            app.egui_ctx = Some(cc.egui_ctx.clone());
+            Ok(Box::new(app))
         }),
     )
 }
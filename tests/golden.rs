@@ -0,0 +1,119 @@
+/*
+    金样本回归: tests/corpus 里是各编码的小样本文件
+    (含 BOM、孤立代理项、混合换行等刁钻情况),
+    通过无界面管道模式转成 UTF-8, 和 golden/ 里的
+    预期字节逐一比对。新增编码或改转码管线时,
+    行为变化会在这里直接暴露
+*/
+
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+fn corpus_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("corpus")
+}
+
+/* 起一个管道模式子进程: stdin 喂原始字节, 收 stdout */
+fn pipe(from: &str, to: &str, input: &[u8]) -> Vec<u8> {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_codetranser"))
+        .args(["--from", from, "--to", to])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .expect("spawn codetranser");
+    child
+        .stdin
+        .take()
+        .expect("stdin")
+        .write_all(input)
+        .expect("write stdin");
+    let out = child.wait_with_output().expect("wait");
+    assert!(out.status.success(), "pipe {} -> {} failed", from, to);
+    out.stdout
+}
+
+/* 文件名形如 "<用例>.<编码标签>.txt", 中段就是 --from 的标签 */
+fn encoding_label(path: &Path) -> String {
+    let name = path.file_name().unwrap().to_string_lossy();
+    let mut parts: Vec<&str> = name.split('.').collect();
+    assert!(parts.len() >= 3, "bad corpus name: {}", name);
+    parts.pop();
+    parts.remove(0);
+    parts.join(".")
+}
+
+#[test]
+fn corpus_to_utf8_matches_golden() {
+    let dir = corpus_dir();
+    let mut checked = 0;
+    for entry in std::fs::read_dir(&dir).expect("corpus dir") {
+        let path = entry.expect("entry").path();
+        if path.extension().is_none_or(|e| e != "txt") {
+            continue;
+        }
+        let label = encoding_label(&path);
+        let stem = path
+            .file_name()
+            .unwrap()
+            .to_string_lossy()
+            .trim_end_matches(".txt")
+            .to_string();
+        let golden_path = dir.join("golden").join(format!("{}.utf8", stem));
+        let input = std::fs::read(&path).expect("read corpus file");
+        let golden =
+            std::fs::read(&golden_path).unwrap_or_else(|_| panic!("missing golden for {}", stem));
+
+        let got = pipe(&label, "utf-8", &input);
+        assert_eq!(
+            got,
+            golden,
+            "{} decoded as {} does not match golden",
+            path.display(),
+            label
+        );
+        checked += 1;
+    }
+    /* 目录空了测试会静默全过, 兜一道底 */
+    assert!(checked >= 10, "only {} corpus files checked", checked);
+}
+
+/* UTF-8 金样本编回原编码再解回来, 应当一字不差 */
+#[test]
+fn legacy_roundtrip_is_lossless() {
+    let dir = corpus_dir();
+    for label in ["gbk", "big5", "shift_jis", "euc-kr", "windows-1251"] {
+        let golden = std::fs::read(dir.join("golden").join(format!("hello.{}.utf8", label)))
+            .expect("golden");
+        let encoded = pipe("utf-8", label, &golden);
+        let back = pipe(label, "utf-8", &encoded);
+        assert_eq!(back, golden, "round-trip through {} lost data", label);
+    }
+}
+
+/* 混合换行原样穿过管道, 管道模式不做换行统一 */
+#[test]
+fn mixed_eol_passes_through() {
+    let dir = corpus_dir();
+    let golden = std::fs::read(dir.join("golden").join("mixed-eol.gbk.utf8")).expect("golden");
+    let text = String::from_utf8(golden.clone()).expect("golden is utf-8");
+    assert!(text.contains("\r\n") && text.contains('\r') && text.contains('\n'));
+    let got = pipe(
+        "gbk",
+        "utf-8",
+        &std::fs::read(dir.join("mixed-eol.gbk.txt")).unwrap(),
+    );
+    assert_eq!(got, golden);
+}
+
+/* 孤立代理项解码成替换字符, 而不是崩掉或吞字节 */
+#[test]
+fn lone_surrogate_becomes_replacement() {
+    let dir = corpus_dir();
+    let input = std::fs::read(dir.join("lone-surrogate.utf-16le.txt")).unwrap();
+    let got = pipe("utf-16le", "utf-8", &input);
+    let text = String::from_utf8(got).expect("output is valid utf-8");
+    assert_eq!(text, "ok \u{FFFD} end\n");
+}